@@ -0,0 +1,479 @@
+use crate::{text_response, HTTP_ADDR};
+use recordadder::{
+    is_placeholder, session, username_problem, AddOutcome, MainObbyCategory, MainObbyOutcome, ObbyIdentity, ObbyMode,
+    RecordStore, Time,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tiny_http::Server;
+
+const USAGE: &str = "\
+Usage: RecordAdder [--session <file>] <command> [args]
+
+Without a command the GUI is started.
+
+Commands:
+  add <obby> <player> <time> [--mode <name> | --bounce] [--any-name]
+                                          Add a run to an obby (Bounceless unless a mode is given)
+  mode list                               Print the obby modes
+  mode set <name> <tag>                   Add an obby mode or change its Studio tag
+  mode remove <name>                      Remove an obby mode that has no runs
+  board <obby> <places|off>               Give an obby a ranked top-N board, or remove it
+  obby list                               Print the obbies with display names and run counts
+  obby rename <obby> <new name>           Rename an obby in all its runs
+  obby merge <obby> <into>                Move every run of an obby to another and remove it
+  obby remove <obby>                      Remove an obby that has no runs
+  obby display <obby> <name>              Set the name shown in the app; an empty name clears it
+  obby match [case] [spaces] [punctuation]
+                                          Treat names differing only in these as the same obby
+  player list                             Print the players with their aliases and Roblox IDs
+  player rename <player> <new name>       Rename a player on every entry; the old name becomes an alias
+  player merge <player> <into>            Move every entry of a player to another
+  player alias <player> <alias>...        Set a player's aliases
+  player id <player> <id|none>            Set or clear a player's Roblox user ID
+  add-main <category> <player> <time> [--any-name]
+                                          Add a Main Obby entry to a configured category
+  category list                           Print the Main Obby categories
+  category set <name> <tag> <slots>       Add a Main Obby category or change its tag and slots
+  category remove <name>                  Remove a Main Obby category; its entries are kept
+                                          in case it is added back
+  import <file> [--dry-run]               Merge a Lua table from RecordModule.get_records,
+                                          or only show what it would change
+  export [--format lua|json] [--out <file>]
+                                          Print or write the current records
+  list                                    Print the current records
+  stats                                   Rank the players by records held
+  serve                                   Serve the JSON export to the Studio plugin
+
+New player names must be valid Roblox usernames unless --any-name is given.
+
+Options:
+  --session <file>   Session file to read and update (default: records.json)
+  --ctt2 <on|off>    Change CTT2 mode of the session before running the command";
+
+/// Runs the command line interface and returns the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    match run_inner(args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            eprintln!();
+            eprintln!("{}", USAGE);
+            2
+        }
+    }
+}
+
+fn run_inner(args: Vec<String>) -> Result<(), String> {
+    let mut session_path = PathBuf::from("records.json");
+    let mut ctt2_mode: Option<bool> = None;
+    let mut rest = Vec::new();
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--session" => {
+                session_path = PathBuf::from(iter.next().ok_or("--session needs a file")?);
+            }
+            "--ctt2" => {
+                ctt2_mode = Some(match iter.next().as_deref() {
+                    Some("on") => true,
+                    Some("off") => false,
+                    _ => return Err("--ctt2 needs 'on' or 'off'".to_string()),
+                });
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ => rest.push(arg),
+        }
+    }
+
+    let Some((command, rest)) = rest.split_first() else {
+        return Err("missing command".to_string());
+    };
+
+    let mut store = load_store(&session_path)?;
+    let loaded_revision = store.revision();
+    if let Some(ctt2_mode) = ctt2_mode {
+        store.set_ctt2_mode(ctt2_mode);
+    }
+
+    match command.as_str() {
+        "add" => {
+            let mut mode = "Bounceless".to_string();
+            let mut any_name = false;
+            let mut positional = Vec::new();
+            let mut args = rest.iter();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--bounce" => mode = "Bounce".to_string(),
+                    "--mode" => mode = args.next().ok_or("--mode needs a name")?.clone(),
+                    "--any-name" => any_name = true,
+                    _ => positional.push(arg),
+                }
+            }
+            let [obby, player, time] = positional[..] else {
+                return Err("add needs <obby> <player> <time>".to_string());
+            };
+            check_player_name(&store, player, any_name)?;
+            let time = parse_time(time)?;
+            if let Some(existing) = store.find_obby(obby)
+                && existing != obby.trim()
+            {
+                eprintln!("note: same obby as '{}'; adding the run there", existing);
+            }
+            match store.add_record_entry(obby.trim(), &mode, player.trim(), time) {
+                AddOutcome::UnknownMode => return Err(format!("unknown mode '{}'", mode)),
+                AddOutcome::BadObbyName(problem) => return Err(problem.to_string()),
+                _ => {}
+            }
+        }
+        "add-main" => {
+            let any_name = rest.iter().any(|a| a == "--any-name");
+            let positional: Vec<&String> = rest.iter().filter(|a| *a != "--any-name").collect();
+            let [category, player, time] = positional[..] else {
+                return Err("add-main needs <category> <player> <time>".to_string());
+            };
+            check_player_name(&store, player, any_name)?;
+            if store.main_obby_category(category).is_none() {
+                return Err(format!("unknown Main Obby category '{}'", category));
+            }
+            let time = parse_time(time)?;
+            match store.add_main_ob_record(player.trim().to_string(), time, category) {
+                MainObbyOutcome::Placed(place) => println!("placed #{}", place + 1),
+                MainObbyOutcome::Improved { place, previous } => {
+                    println!("improved from {}s, now #{}", previous, place + 1)
+                }
+                MainObbyOutcome::Duplicate => println!("already on the leaderboard"),
+                MainObbyOutcome::SlowerThanOwn(best) => {
+                    println!("ignored: slower than their listed {}s", best)
+                }
+                MainObbyOutcome::TooSlow => println!("archived: too slow for the leaderboard"),
+                MainObbyOutcome::UnknownCategory => unreachable!("category was checked above"),
+            }
+        }
+        "mode" => {
+            let mut modes = store.modes().to_vec();
+            match rest.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["list"] => {
+                    for mode in &modes {
+                        println!("{} - tag {}", mode.name, mode.tag);
+                    }
+                }
+                ["set", name, tag] => {
+                    let mode = ObbyMode::new(name, tag);
+                    match modes.iter_mut().find(|m| m.name == name) {
+                        Some(existing) => *existing = mode,
+                        None => modes.push(mode),
+                    }
+                    store.set_modes(modes)?;
+                }
+                ["remove", name] => {
+                    if !modes.iter().any(|m| m.name == name) {
+                        return Err(format!("unknown mode '{}'", name));
+                    }
+                    modes.retain(|m| m.name != name);
+                    store.set_modes(modes)?;
+                }
+                _ => return Err("mode needs list, set <name> <tag> or remove <name>".to_string()),
+            }
+        }
+        "board" => {
+            let [obby, places] = rest else {
+                return Err("board needs <obby> <places|off>".to_string());
+            };
+            let size = match places.as_str() {
+                "off" => None,
+                n => Some(
+                    n.parse::<usize>()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("'{}' is not a valid number of places", n))?,
+                ),
+            };
+            store.set_board_size(obby, size)?;
+        }
+        "obby" => match rest.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["list"] => {
+                for obby in store.obby_names() {
+                    let name = store.display_name(obby);
+                    let shown = if name == obby.as_str() { String::new() } else { format!(" ({})", name) };
+                    println!("{}{} - {} runs", obby, shown, store.run_count(obby));
+                }
+            }
+            ["rename", obby, name] => store.rename_obby(obby, name)?,
+            ["merge", obby, into] => store.merge_obby(obby, into)?,
+            ["remove", obby] => store.remove_obby(obby)?,
+            ["match", ref ignored @ ..] => {
+                let mut identity = ObbyIdentity {
+                    ignore_case: false,
+                    ignore_whitespace: false,
+                    ignore_punctuation: false,
+                };
+                for &part in ignored {
+                    match part {
+                        "case" => identity.ignore_case = true,
+                        "spaces" => identity.ignore_whitespace = true,
+                        "punctuation" => identity.ignore_punctuation = true,
+                        other => return Err(format!("'{}' is not case, spaces or punctuation", other)),
+                    }
+                }
+                store.set_obby_identity(identity);
+                for group in store.obby_collisions() {
+                    let names: Vec<&str> = group.iter().map(|name| name.as_str()).collect();
+                    eprintln!("warning: {} look like the same obby; merge them", names.join(" / "));
+                }
+            }
+            ["display", obby, name] => {
+                if !store.obby_names().iter().any(|o| o.as_str() == obby) {
+                    return Err(format!("unknown obby '{}'", obby));
+                }
+                store.set_display_name(obby, name);
+            }
+            _ => {
+                return Err(
+                    "obby needs list, rename <obby> <new name>, merge <obby> <into>, remove <obby>, \
+                     display <obby> <name> or match [case] [spaces] [punctuation]"
+                        .to_string(),
+                )
+            }
+        },
+        "player" => match rest.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["list"] => {
+                for name in store.player_names() {
+                    let mut line = name.to_string();
+                    if let Some(player) = store.player(name) {
+                        if !player.aliases.is_empty() {
+                            line += &format!(" (aka {})", player.aliases.join(", "));
+                        }
+                        if let Some(id) = player.roblox_id {
+                            line += &format!(" - Roblox ID {}", id);
+                        }
+                    }
+                    println!("{}", line);
+                }
+            }
+            ["rename", player, name] => store.rename_player(player, name)?,
+            ["merge", player, into] => store.merge_player(player, into)?,
+            ["alias", player, ref aliases @ ..] => {
+                let id = store.player(player).and_then(|p| p.roblox_id);
+                let aliases = aliases.iter().map(|a| a.to_string()).collect();
+                store.set_player_info(player, aliases, id)?;
+            }
+            ["id", player, id] => {
+                let id = match id {
+                    "none" => None,
+                    id => Some(id.parse::<u64>().map_err(|_| format!("'{}' is not a Roblox user ID", id))?),
+                };
+                let aliases = store.player(player).map(|p| p.aliases.clone()).unwrap_or_default();
+                store.set_player_info(player, aliases, id)?;
+            }
+            _ => {
+                return Err(
+                    "player needs list, rename <player> <new name>, merge <player> <into>, \
+                     alias <player> <alias>... or id <player> <id|none>"
+                        .to_string(),
+                )
+            }
+        },
+        "category" => {
+            let mut categories = store.main_obby_categories().to_vec();
+            match rest.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+                ["list"] => {
+                    for category in &categories {
+                        println!("{} - tag {} - {} slots", category.name, category.tag, category.cap);
+                    }
+                }
+                ["set", name, tag, slots] => {
+                    let cap = slots
+                        .parse::<usize>()
+                        .map_err(|_| format!("'{}' is not a valid number of slots", slots))?;
+                    let category = MainObbyCategory::new(name, tag, cap);
+                    match categories.iter_mut().find(|c| c.name == name) {
+                        Some(existing) => *existing = category,
+                        None => categories.push(category),
+                    }
+                    store.set_main_obby_categories(categories)?;
+                }
+                ["remove", name] => {
+                    if !categories.iter().any(|c| c.name == name) {
+                        return Err(format!("unknown Main Obby category '{}'", name));
+                    }
+                    categories.retain(|c| c.name != name);
+                    store.set_main_obby_categories(categories)?;
+                }
+                _ => return Err("category needs list, set <name> <tag> <slots> or remove <name>".to_string()),
+            }
+        }
+        "import" => {
+            let dry_run = rest.iter().any(|a| a == "--dry-run");
+            let positional: Vec<&String> = rest.iter().filter(|a| *a != "--dry-run").collect();
+            let [file] = positional[..] else {
+                return Err("import needs <file>".to_string());
+            };
+            let content =
+                fs::read_to_string(file).map_err(|e| format!("could not read {}: {}", file, e))?;
+            let report = if dry_run {
+                store.preview_import(&content)
+            } else {
+                store.import_lua(&content)
+            }
+            .map_err(|e| format!("could not import {}: {}", file, e))?;
+
+            if dry_run {
+                for item in &report.items {
+                    println!("{}: {}", item.action(), item.describe());
+                }
+            }
+            println!("{}", report.summary());
+            for item in report.rejected() {
+                eprintln!("rejected: {}", item.describe());
+            }
+        }
+        "export" => {
+            let mut format = "lua".to_string();
+            let mut out: Option<String> = None;
+            let mut options = rest.iter();
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--format" => format = options.next().ok_or("--format needs lua or json")?.clone(),
+                    "--out" => out = Some(options.next().ok_or("--out needs a file")?.clone()),
+                    other => return Err(format!("unknown export option '{}'", other)),
+                }
+            }
+
+            let output = match format.as_str() {
+                "lua" => store.generate_lua_export(),
+                "json" => store.generate_json_export(),
+                other => return Err(format!("unknown export format '{}'", other)),
+            };
+
+            match out {
+                Some(file) => {
+                    fs::write(&file, output).map_err(|e| format!("could not write {}: {}", file, e))?
+                }
+                None => println!("{}", output),
+            }
+        }
+        "list" => print_records(&store),
+        "stats" => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            for (i, stats) in store.player_stats(now).iter().enumerate() {
+                println!("{}. {}", i + 1, stats.describe());
+            }
+        }
+        "serve" => serve(&session_path)?,
+        other => return Err(format!("unknown command '{}'", other)),
+    }
+
+    if store.revision() != loaded_revision {
+        session::save(&session_path, &store)
+            .map_err(|e| format!("could not save {}: {}", session_path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Refuses empty names and, unless `any_name` is set, new names that are not
+/// valid Roblox usernames. Known players and aliases always pass.
+fn check_player_name(store: &RecordStore, name: &str, any_name: bool) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("player name is empty".to_string());
+    }
+    let known = store.player(name).is_some() || store.player_names().iter().any(|p| p.as_str() == name);
+    match username_problem(name) {
+        Some(problem) if !known && !any_name => Err(format!("{}: {} (pass --any-name to keep it)", name, problem)),
+        _ => Ok(()),
+    }
+}
+
+fn parse_time(text: &str) -> Result<Time, String> {
+    text.parse::<Time>().map_err(|e| e.to_string())
+}
+
+fn load_store(path: &Path) -> Result<RecordStore, String> {
+    if !path.exists() {
+        return Ok(RecordStore::new());
+    }
+
+    session::load(path).map_err(|e| format!("could not open {}: {}", path.display(), e))
+}
+
+fn print_records(store: &RecordStore) {
+    for record in store.current_records() {
+        println!(
+            "{} - {} - {} - {}s{}",
+            record.obby,
+            record.mode,
+            record.player,
+            record.time,
+            if record.is_placeholder() { " (placeholder)" } else { "" }
+        );
+    }
+
+    for obby in store.obby_names() {
+        for mode in store.modes() {
+            let board = store.board(obby, &mode.name);
+            if board.is_empty() {
+                continue;
+            }
+            println!();
+            println!("{} - {} board:", obby, mode.name);
+            for (i, (p, t)) in board.iter().enumerate() {
+                println!("{}. {} - {}s", i + 1, p, t);
+            }
+        }
+    }
+
+    if store.ctt2_mode() {
+        for category in store.main_obby_categories() {
+            let list = store.main_obby(&category.name).unwrap_or_default();
+            if list.is_empty() {
+                continue;
+            }
+            println!();
+            println!("Main Obby {}:", category.name);
+            for (i, (p, t)) in list.iter().enumerate() {
+                let mark = if is_placeholder(p, *t) { " (placeholder)" } else { "" };
+                println!("{}. {} - {}s{}", i + 1, p, t, mark);
+            }
+            let archived = store.main_obby_archive(&category.name).unwrap_or_default().len();
+            if archived > 0 {
+                println!("({} more archived below the cap)", archived);
+            }
+        }
+    }
+
+    let placeholders = store.placeholder_count();
+    if placeholders > 0 {
+        eprintln!();
+        eprintln!(
+            "warning: {} entries look like placeholders from an old import (empty name or a time of exactly 9999s); \
+             check and delete them",
+            placeholders
+        );
+    }
+}
+
+fn serve(session_path: &Path) -> Result<(), String> {
+    let server = Server::http(HTTP_ADDR).map_err(|e| format!("could not listen on {}: {}", HTTP_ADDR, e))?;
+    println!("Serving {} on http://{}", session_path.display(), HTTP_ADDR);
+
+    for request in server.incoming_requests() {
+        // Re-read the session on every request so `add` and `import` runs from
+        // other processes show up without restarting the server.
+        let data = load_store(session_path)
+            .map(|store| store.generate_json_export())
+            .unwrap_or_else(|_| "{}".to_string());
+        let _ = request.respond(text_response(data));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,230 @@
+use crate::luau::{Table, Value};
+use crate::store::RecordStore;
+use crate::time::Time;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize)]
+struct ExportTable {
+    #[serde(rename = "CTT2Mode")]
+    ctt2_mode: bool,
+    #[serde(flatten)]
+    obbies: BTreeMap<String, HashMap<String, (String, Time)>>,
+    /// Leaderboard folder inside an obby's folder for each mode.
+    #[serde(rename = "ModeTags", skip_serializing_if = "Option::is_none")]
+    mode_tags: Option<BTreeMap<String, String>>,
+    #[serde(rename = "Boards", skip_serializing_if = "BTreeMap::is_empty")]
+    boards: BTreeMap<String, BTreeMap<String, Vec<(String, Time)>>>,
+    #[serde(rename = "MainObby", skip_serializing_if = "Option::is_none")]
+    main_obby: Option<HashMap<String, Vec<(String, Time)>>>,
+    /// Folder under `MISC.LBS.MO` for each Main Obby category.
+    #[serde(rename = "MainObbyTags", skip_serializing_if = "Option::is_none")]
+    main_obby_tags: Option<BTreeMap<String, String>>,
+}
+
+impl RecordStore {
+    /// Current record holders grouped by obby and mode.
+    fn record_map(&self) -> BTreeMap<String, HashMap<String, (String, Time)>> {
+        let mut map: BTreeMap<String, HashMap<String, (String, Time)>> = BTreeMap::new();
+
+        for r in self.current_records() {
+            map.entry(r.obby.clone())
+                .or_default()
+                .insert(r.mode.clone(), (r.player.clone(), r.time));
+        }
+
+        map
+    }
+
+    /// Ranked boards grouped by obby and mode, leaving out empty ones.
+    fn board_map(&self) -> BTreeMap<String, BTreeMap<String, Vec<(String, Time)>>> {
+        let mut map: BTreeMap<String, BTreeMap<String, Vec<(String, Time)>>> = BTreeMap::new();
+
+        for obby in self.obby_names() {
+            for mode in self.modes() {
+                let board = self.board(obby, &mode.name);
+                if !board.is_empty() {
+                    map.entry(obby.clone()).or_default().insert(mode.name.clone(), board);
+                }
+            }
+        }
+
+        map
+    }
+
+    /// The JSON served to the Studio plugin for real-time updates.
+    pub fn generate_json_export(&self) -> String {
+        let obbies = self.record_map();
+        let mode_tags = self.ctt2_mode().then(|| {
+            self.modes()
+                .iter()
+                .map(|m| (m.name.clone(), m.tag.clone()))
+                .collect()
+        });
+
+        let (main_obby, main_obby_tags) = if self.ctt2_mode() {
+            let mut mo = HashMap::new();
+            let mut tags = BTreeMap::new();
+            // Empty categories are sent too, so Studio clears their slots.
+            for category in self.main_obby_categories() {
+                let list = self.main_obby(&category.name).unwrap_or_default();
+                mo.insert(category.name.clone(), list.to_vec());
+                tags.insert(category.name.clone(), category.tag.clone());
+            }
+            (Some(mo), Some(tags))
+        } else {
+            (None, None)
+        };
+
+        let export = ExportTable {
+            ctt2_mode: self.ctt2_mode(),
+            obbies,
+            mode_tags,
+            boards: self.board_map(),
+            main_obby,
+            main_obby_tags,
+        };
+
+        serde_json::to_string(&export).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    /// The Lua table pasted into `RecordModule.add` in Studio.
+    pub fn generate_lua_export(&self) -> String {
+        let mut root = Table::new();
+        root.field("CTT2Mode", Value::Bool(self.ctt2_mode()));
+
+        for (obby, types) in self.record_map() {
+            let mut modes = Table::new();
+            for mode in self.modes() {
+                if let Some((player, time)) = types.get(&mode.name) {
+                    modes.field(&mode.name, entry(player, *time));
+                }
+            }
+            root.field(&obby, Value::Table(modes));
+        }
+
+        let boards = self.board_map();
+        if !boards.is_empty() {
+            let mut table = Table::new();
+            for (obby, lists) in boards {
+                let mut modes = Table::new();
+                for mode in self.modes() {
+                    if let Some(list) = lists.get(&mode.name) {
+                        let mut entries = Table::new();
+                        for (p, t) in list {
+                            entries.push(entry(p, *t));
+                        }
+                        modes.field(&mode.name, Value::Table(entries));
+                    }
+                }
+                table.field(&obby, Value::Table(modes));
+            }
+            root.field("Boards", Value::Table(table));
+        }
+
+        if self.ctt2_mode() {
+            let mut mode_tags = Table::new();
+            for mode in self.modes() {
+                mode_tags.field(&mode.name, Value::String(mode.tag.clone()));
+            }
+            root.field("ModeTags", Value::Table(mode_tags));
+
+            let mut main_obby = Table::new();
+            let mut tags = Table::new();
+
+            for category in self.main_obby_categories() {
+                let mut entries = Table::new();
+                for (p, t) in self.main_obby(&category.name).unwrap_or_default() {
+                    entries.push(entry(p, *t));
+                }
+                main_obby.field(&category.name, Value::Table(entries));
+                tags.field(&category.name, Value::String(category.tag.clone()));
+            }
+
+            root.field("MainObby", Value::Table(main_obby));
+            root.field("MainObbyTags", Value::Table(tags));
+        }
+
+        root.to_luau()
+    }
+}
+
+/// A `{ player, time }` pair as the leaderboards store it.
+fn entry(player: &str, time: Time) -> Value {
+    let mut pair = Table::new();
+    pair.push(Value::String(player.to_string()));
+    pair.push(Value::Number(time.as_secs_f64()));
+    Value::Table(pair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lua_export_round_trips_awkward_names() {
+        let names = [
+            "quote\"d",
+            "back\\slash",
+            "new\nline",
+            "]] .. os.exit() .. [[",
+            "\", os.exit(), \"",
+            "tab\tand \u{7}bell",
+            "ünïcödé",
+        ];
+
+        let mut store = RecordStore::new();
+        store.set_ctt2_mode(true);
+        for (i, name) in names.iter().enumerate() {
+            let mode = if i % 2 == 0 { "Bounce" } else { "Bounceless" };
+            store.add_record_entry(name, mode, name, Time::from_millis(10_000 + i as u64 * 1000));
+        }
+        store.add_main_ob_record(names[1].to_string(), Time::from_millis(99_500), "Bounce");
+
+        let mut imported = RecordStore::new();
+        imported.import_lua(&store.generate_lua_export()).unwrap();
+
+        for name in names {
+            let record = imported
+                .current_records()
+                .find(|r| r.obby == name)
+                .unwrap_or_else(|| panic!("{:?} was not imported", name));
+            assert_eq!(record.player, name);
+        }
+        assert_eq!(
+            imported.main_obby("Bounce").unwrap(),
+            &[(names[1].to_string(), Time::from_millis(99_500))]
+        );
+    }
+
+    #[test]
+    fn empty_main_obby_categories_are_exported_so_studio_clears_them() {
+        let mut store = RecordStore::new();
+        store.set_ctt2_mode(true);
+        store.add_main_ob_record("amy".to_string(), Time::from_millis(99_500), "Bounce");
+        store.delete_main_obby_entry("Bounce", 0);
+
+        let json: serde_json::Value = serde_json::from_str(&store.generate_json_export()).unwrap();
+        assert_eq!(json["MainObby"]["Bounce"], serde_json::json!([]));
+        assert!(store.generate_lua_export().contains("[\"Bounce\"] = {}"));
+    }
+
+    #[test]
+    fn boards_are_exported_as_ranked_lists() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Tower", "Bounce", "amy", Time::from_millis(12_000));
+        store.add_record_entry("Tower", "Bounce", "bob", Time::from_millis(11_000));
+        store.add_record_entry("Tower", "Bounce", "cat", Time::from_millis(13_000));
+        store.set_board_size("Tower", Some(2)).unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&store.generate_json_export()).unwrap();
+        assert_eq!(
+            json["Boards"]["Tower"]["Bounce"],
+            serde_json::json!([["bob", 11.0], ["amy", 12.0]])
+        );
+
+        let mut imported = RecordStore::new();
+        imported.import_lua(&store.generate_lua_export()).unwrap();
+        assert_eq!(imported.runs_for("Tower", "Bounce").len(), 2);
+    }
+}
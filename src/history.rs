@@ -0,0 +1,121 @@
+use crate::store::{Correction, MainObbyCategory, MainObbyRule, ObbyIdentity, ObbyMode, Player, Record};
+use crate::time::Time;
+
+const MAX_HISTORY: usize = 200;
+
+/// A single reversible change to the record state.
+#[derive(Clone)]
+pub enum Change {
+    RecordInserted { index: usize, record: Record },
+    RecordRemoved { index: usize, record: Record },
+    RecordEdited { index: usize, old: Record, new: Record },
+    CorrectionLogged(Correction),
+    MainObbyChanged {
+        category: String,
+        old: Vec<(String, Time)>,
+        new: Vec<(String, Time)>,
+    },
+    ModesChanged { old: Vec<ObbyMode>, new: Vec<ObbyMode> },
+    MainObbyCategoriesChanged {
+        old: Vec<MainObbyCategory>,
+        new: Vec<MainObbyCategory>,
+    },
+    BoardSizeChanged {
+        obby: String,
+        old: Option<usize>,
+        new: Option<usize>,
+    },
+    ObbyNameAdded(String),
+    ObbyNameRemoved(String),
+    ObbyIdentityChanged { old: ObbyIdentity, new: ObbyIdentity },
+    PlayersChanged { old: Vec<Player>, new: Vec<Player> },
+    DisplayNameChanged {
+        obby: String,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    MainObbyRuleChanged { old: MainObbyRule, new: MainObbyRule },
+}
+
+/// One user action, e.g. adding a record or a whole clipboard import.
+#[derive(Clone)]
+pub struct Command {
+    pub label: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    open: Option<Command>,
+    /// How many `begin` calls are still waiting for their `commit`.
+    depth: usize,
+}
+
+impl History {
+    /// Starts grouping changes into one command until `commit` is called.
+    /// Nested calls join the outer command, so a store method that groups its
+    /// own changes can be called inside a larger action.
+    pub fn begin(&mut self, label: &str) {
+        self.depth += 1;
+        if self.open.is_none() {
+            self.open = Some(Command {
+                label: label.to_string(),
+                changes: Vec::new(),
+            });
+        }
+    }
+
+    pub fn push(&mut self, change: Change) {
+        match &mut self.open {
+            Some(command) => command.changes.push(change),
+            None => self.finish(Command {
+                label: "Edit".to_string(),
+                changes: vec![change],
+            }),
+        }
+    }
+
+    pub fn commit(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth > 0 {
+            return;
+        }
+        if let Some(command) = self.open.take()
+            && !command.changes.is_empty()
+        {
+            self.finish(command);
+        }
+    }
+
+    fn finish(&mut self, command: Command) {
+        self.undo.push(command);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// Moves the latest command to the redo stack and returns it so the caller
+    /// can revert its changes.
+    pub fn undo(&mut self) -> Option<Command> {
+        let command = self.undo.pop()?;
+        self.redo.push(command.clone());
+        Some(command)
+    }
+
+    pub fn redo(&mut self) -> Option<Command> {
+        let command = self.redo.pop()?;
+        self.undo.push(command.clone());
+        Some(command)
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.last().map(|c| c.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|c| c.label.as_str())
+    }
+}
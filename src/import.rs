@@ -0,0 +1,742 @@
+use crate::luau::{self, Key, ParseError, Table, Value};
+use crate::store::{obby_name_problem, AddOutcome, MainObbyCategory, MainObbyOutcome, ObbyMode, RecordStore};
+use crate::time::Time;
+
+/// What happened, or would happen, to one entry of an import.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportOutcome {
+    Added,
+    /// Beat the current holder.
+    Improved,
+    /// Valid, but did not change any record.
+    Ignored { reason: String },
+    /// Could not be read at all.
+    Rejected { reason: String },
+}
+
+/// Where a valid entry goes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImportTarget {
+    Obby { obby: String, mode: String },
+    MainObby { category: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportItem {
+    /// Where the entry was found, e.g. "Lava Tower / Bounce" or "Main Obby / NoPlat".
+    pub target: String,
+    /// None for rejected entries.
+    pub destination: Option<ImportTarget>,
+    pub player: Option<String>,
+    pub time: Option<Time>,
+    /// The record holder before this entry was applied.
+    pub current: Option<(String, Time)>,
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImportReport {
+    pub items: Vec<ImportItem>,
+}
+
+impl ImportReport {
+    pub fn added(&self) -> impl Iterator<Item = &ImportItem> {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.outcome, ImportOutcome::Added))
+    }
+
+    pub fn improved(&self) -> impl Iterator<Item = &ImportItem> {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.outcome, ImportOutcome::Improved))
+    }
+
+    pub fn ignored(&self) -> impl Iterator<Item = &ImportItem> {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.outcome, ImportOutcome::Ignored { .. }))
+    }
+
+    pub fn rejected(&self) -> impl Iterator<Item = &ImportItem> {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.outcome, ImportOutcome::Rejected { .. }))
+    }
+
+    /// One line per count, e.g. for the command line.
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} improved, {} ignored, {} rejected",
+            self.added().count(),
+            self.improved().count(),
+            self.ignored().count(),
+            self.rejected().count()
+        )
+    }
+
+    fn reject(&mut self, target: String, reason: String) {
+        self.items.push(ImportItem {
+            target,
+            destination: None,
+            player: None,
+            time: None,
+            current: None,
+            outcome: ImportOutcome::Rejected { reason },
+        });
+    }
+
+    fn accept(&mut self, target: String, destination: ImportTarget, player: String, time: Time) {
+        self.items.push(ImportItem {
+            target,
+            destination: Some(destination),
+            player: Some(player),
+            time: Some(time),
+            current: None,
+            outcome: ImportOutcome::Added,
+        });
+    }
+}
+
+impl ImportItem {
+    /// A short description of the outcome for the preview table.
+    pub fn action(&self) -> String {
+        match &self.outcome {
+            ImportOutcome::Added => "add".to_string(),
+            ImportOutcome::Improved => "new record".to_string(),
+            ImportOutcome::Ignored { reason } => format!("ignore: {}", reason),
+            ImportOutcome::Rejected { reason } => format!("reject: {}", reason),
+        }
+    }
+
+    /// A human readable line describing the entry and its outcome.
+    pub fn describe(&self) -> String {
+        let mut line = self.target.clone();
+        if let Some(player) = &self.player {
+            line.push_str(&format!(" - {}", player));
+        }
+        if let Some(time) = self.time {
+            line.push_str(&format!(" - {}s", time));
+        }
+
+        match &self.outcome {
+            ImportOutcome::Added => {}
+            ImportOutcome::Improved => {
+                if let Some((player, time)) = &self.current {
+                    line.push_str(&format!(" (was {} - {}s)", player, time));
+                }
+            }
+            ImportOutcome::Ignored { reason } | ImportOutcome::Rejected { reason } => {
+                line.push_str(&format!(" ({})", reason));
+            }
+        }
+
+        line
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Nil => "nil",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Table(_) => "a table",
+    }
+}
+
+/// Reads a `{ player, time }` pair.
+fn read_entry(value: &Value) -> Result<(String, Time), String> {
+    let Value::Table(entry) = value else {
+        return Err(format!("expected {{ player, time }}, found {}", type_name(value)));
+    };
+
+    let player = match entry.index(1) {
+        Some(Value::String(p)) if p.trim().is_empty() => return Err("player name is empty".to_string()),
+        Some(Value::String(p)) => p.clone(),
+        Some(other) => return Err(format!("player name is {}, not a string", type_name(other))),
+        None => return Err("player name is missing".to_string()),
+    };
+    let time = match entry.index(2) {
+        Some(Value::Number(t)) => {
+            Time::from_secs_f64(*t).ok_or_else(|| format!("time {} is not a valid run time", t))?
+        }
+        // Hand-written tables may hold times like "1:23.456".
+        Some(Value::String(t)) => t.parse::<Time>().map_err(|e| e.to_string())?,
+        Some(other) => return Err(format!("time is {}, not a number", type_name(other))),
+        None => return Err("time is missing".to_string()),
+    };
+
+    if time.is_placeholder() {
+        return Err(format!("time {}s looks like a placeholder", time));
+    }
+
+    Ok((player, time))
+}
+
+/// Reads the entries of a Lua table without touching any records. Valid
+/// entries come back as `Added` until they are applied.
+fn read_import(
+    content: &str,
+    modes: &[ObbyMode],
+    categories: &[MainObbyCategory],
+) -> Result<ImportReport, ParseError> {
+    let table = match luau::parse(content)? {
+        Value::Table(table) => table,
+        other => {
+            return Err(ParseError {
+                message: format!("expected a table, found {}", type_name(&other)),
+                line: 1,
+                column: 1,
+            });
+        }
+    };
+
+    let mut report = ImportReport::default();
+
+    for value in table.sequence() {
+        report.reject(
+            "(unnamed)".to_string(),
+            format!("{} without an obby name", type_name(value)),
+        );
+    }
+
+    for (key, value) in table.pairs() {
+        let name = match key {
+            Key::String(name) => name,
+            Key::Number(n) => {
+                report.reject(format!("[{}]", n), "obby names must be strings".to_string());
+                continue;
+            }
+            Key::Bool(b) => {
+                report.reject(format!("[{}]", b), "obby names must be strings".to_string());
+                continue;
+            }
+        };
+
+        // Settings of the export; anything else under these names is an obby
+        // that was allowed to take a reserved name.
+        match (name.as_str(), value) {
+            ("CTT2Mode", Value::Bool(_)) => continue,
+            ("ModeTags" | "MainObbyTags", Value::Table(tags)) if is_tag_table(tags) => continue,
+            ("CTT2Mode" | "ModeTags" | "MainObbyTags", _) => {
+                report.reject(name.clone(), obby_name_problem(name).unwrap_or_default().to_string());
+                continue;
+            }
+            _ => {}
+        }
+
+        let Value::Table(inner) = value else {
+            report.reject(
+                name.clone(),
+                format!("expected a table, found {}", type_name(value)),
+            );
+            continue;
+        };
+
+        if name == "MainObby" {
+            read_main_obby(inner, categories, &mut report);
+        } else if name == "Boards" {
+            read_boards(inner, modes, &mut report);
+        } else {
+            read_obby(name, inner, modes, &mut report);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Whether a table maps names to Studio tags, as `ModeTags` and
+/// `MainObbyTags` do.
+fn is_tag_table(table: &Table) -> bool {
+    table.sequence().next().is_none() && table.pairs().all(|(_, tag)| matches!(tag, Value::String(_)))
+}
+
+/// Names that are only whitespace are shown as "(unnamed)" in the report.
+fn unnamed_or(name: &str) -> String {
+    if name.trim().is_empty() { "(unnamed)".to_string() } else { name.to_string() }
+}
+
+/// Modes are matched by name, or by Studio tag like Main Obby categories.
+fn find_mode<'a>(modes: &'a [ObbyMode], key: &str) -> Option<&'a ObbyMode> {
+    modes
+        .iter()
+        .find(|mode| mode.name == key)
+        .or_else(|| modes.iter().find(|mode| mode.tag == key))
+}
+
+fn read_obby(obby: &str, entries: &Table, modes: &[ObbyMode], report: &mut ImportReport) {
+    if let Some(problem) = obby_name_problem(obby) {
+        report.reject(unnamed_or(obby), problem.to_string());
+        return;
+    }
+
+    for value in entries.sequence() {
+        report.reject(
+            obby.to_string(),
+            format!("{} without a mode name", type_name(value)),
+        );
+    }
+
+    for (mode, data) in entries.pairs() {
+        let mode = match mode {
+            Key::String(m) => {
+                match find_mode(modes, m) {
+                    Some(mode) => &mode.name,
+                    None => {
+                        report.reject(format!("{} / {}", obby, m), "unknown mode".to_string());
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                report.reject(obby.to_string(), "mode names must be strings".to_string());
+                continue;
+            }
+        };
+        let target = format!("{} / {}", obby, mode);
+
+        match read_entry(data) {
+            Ok((player, time)) => {
+                let destination = ImportTarget::Obby {
+                    obby: obby.to_string(),
+                    mode: mode.clone(),
+                };
+                report.accept(target, destination, player, time);
+            }
+            Err(reason) => report.reject(target, reason),
+        }
+    }
+}
+
+/// Every entry of a ranked board is read as a run of its obby/mode.
+fn read_boards(boards: &Table, modes: &[ObbyMode], report: &mut ImportReport) {
+    for (obby, lists) in boards.pairs() {
+        let Key::String(obby) = obby else {
+            report.reject("Boards".to_string(), "obby names must be strings".to_string());
+            continue;
+        };
+        if let Some(problem) = obby_name_problem(obby) {
+            report.reject(format!("Boards / {}", unnamed_or(obby)), problem.to_string());
+            continue;
+        }
+        let Value::Table(lists) = lists else {
+            report.reject(
+                format!("Boards / {}", obby),
+                format!("expected a table, found {}", type_name(lists)),
+            );
+            continue;
+        };
+
+        for value in lists.sequence() {
+            report.reject(
+                format!("Boards / {}", obby),
+                format!("{} without a mode name", type_name(value)),
+            );
+        }
+
+        for (mode, list) in lists.pairs() {
+            let mode = match mode {
+                Key::String(m) => match find_mode(modes, m) {
+                    Some(mode) => &mode.name,
+                    None => {
+                        report.reject(format!("{} / {}", obby, m), "unknown mode".to_string());
+                        continue;
+                    }
+                },
+                _ => {
+                    report.reject(obby.clone(), "mode names must be strings".to_string());
+                    continue;
+                }
+            };
+            let target = format!("{} / {}", obby, mode);
+
+            let Value::Table(entries) = list else {
+                report.reject(target, format!("expected a list, found {}", type_name(list)));
+                continue;
+            };
+            for entry in entries.sequence() {
+                match read_entry(entry) {
+                    Ok((player, time)) => {
+                        let destination = ImportTarget::Obby {
+                            obby: obby.clone(),
+                            mode: mode.clone(),
+                        };
+                        report.accept(target.clone(), destination, player, time);
+                    }
+                    Err(reason) => report.reject(target.clone(), reason),
+                }
+            }
+        }
+    }
+}
+
+/// Categories are matched by name, or by Studio tag for tables read straight
+/// from the `MISC.LBS.MO` folders.
+fn read_main_obby(main_ob: &Table, categories: &[MainObbyCategory], report: &mut ImportReport) {
+    for (key, value) in main_ob.pairs() {
+        let category = match key {
+            Key::String(c) => {
+                let known = categories
+                    .iter()
+                    .find(|category| category.name == *c)
+                    .or_else(|| categories.iter().find(|category| category.tag == *c));
+                match known {
+                    Some(category) => &category.name,
+                    None => {
+                        report.reject(format!("Main Obby / {}", c), "unknown category".to_string());
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                report.reject("Main Obby".to_string(), "category names must be strings".to_string());
+                continue;
+            }
+        };
+        let target = format!("Main Obby / {}", category);
+
+        let Value::Table(entries) = value else {
+            report.reject(target, format!("expected a list, found {}", type_name(value)));
+            continue;
+        };
+
+        for entry in entries.sequence() {
+            match read_entry(entry) {
+                Ok((player, time)) => {
+                    let destination = ImportTarget::MainObby {
+                        category: category.to_string(),
+                    };
+                    report.accept(target.clone(), destination, player, time);
+                }
+                Err(reason) => report.reject(target.clone(), reason),
+            }
+        }
+    }
+}
+
+impl RecordStore {
+    /// Merges a Lua table in the `generate_lua_export` /
+    /// `RecordModule.get_records` format into the current records and reports
+    /// what happened to every entry.
+    ///
+    /// The text is parsed as data only; it is never executed.
+    pub fn import_lua(&mut self, content: &str) -> Result<ImportReport, ParseError> {
+        let read = read_import(content, self.modes(), self.main_obby_categories())?;
+        Ok(self.apply_import(read.items.iter()))
+    }
+
+    /// Reports what `import_lua` would do, without changing anything.
+    pub fn preview_import(&self, content: &str) -> Result<ImportReport, ParseError> {
+        let read = read_import(content, self.modes(), self.main_obby_categories())?;
+        Ok(self.scratch_copy().apply_import(read.items.iter()))
+    }
+
+    /// Applies entries from a preview, e.g. only the ones a user approved, as
+    /// one undo step. Rejected entries are carried over unchanged.
+    pub fn apply_import<'a>(&mut self, items: impl Iterator<Item = &'a ImportItem>) -> ImportReport {
+        let mut report = ImportReport::default();
+
+        self.begin("Import");
+        for item in items {
+            let (Some(destination), Some(player), Some(time)) = (&item.destination, &item.player, item.time) else {
+                report.items.push(item.clone());
+                continue;
+            };
+
+            let mut applied = item.clone();
+            match destination {
+                ImportTarget::Obby { obby, mode } => {
+                    let outcome = self.add_record_entry(obby, mode, player, time);
+                    (applied.current, applied.outcome) = match outcome {
+                        AddOutcome::Added => (None, ImportOutcome::Added),
+                        AddOutcome::Improved(previous) => {
+                            (Some((previous.player, previous.time)), ImportOutcome::Improved)
+                        }
+                        AddOutcome::Slower(holder) => (
+                            Some((holder.player.clone(), holder.time)),
+                            ImportOutcome::Ignored {
+                                reason: format!("slower than {} - {}s", holder.player, holder.time),
+                            },
+                        ),
+                        AddOutcome::Duplicate => (
+                            Some((player.clone(), time)),
+                            ImportOutcome::Ignored {
+                                reason: "already recorded".to_string(),
+                            },
+                        ),
+                        AddOutcome::UnknownMode => (
+                            None,
+                            ImportOutcome::Rejected {
+                                reason: "unknown mode".to_string(),
+                            },
+                        ),
+                        AddOutcome::BadObbyName(problem) => (
+                            None,
+                            ImportOutcome::Rejected {
+                                reason: problem.to_string(),
+                            },
+                        ),
+                    };
+                }
+                ImportTarget::MainObby { category } => {
+                    let outcome = self.add_main_ob_record(player.clone(), time, category);
+                    (applied.current, applied.outcome) = match outcome {
+                        MainObbyOutcome::Placed(_) => (None, ImportOutcome::Added),
+                        MainObbyOutcome::Improved { previous, .. } => {
+                            (Some((player.clone(), previous)), ImportOutcome::Improved)
+                        }
+                        MainObbyOutcome::Duplicate => (
+                            Some((player.clone(), time)),
+                            ImportOutcome::Ignored {
+                                reason: "already on the leaderboard".to_string(),
+                            },
+                        ),
+                        MainObbyOutcome::SlowerThanOwn(best) => (
+                            Some((player.clone(), best)),
+                            ImportOutcome::Ignored {
+                                reason: format!("slower than their listed {}s", best),
+                            },
+                        ),
+                        MainObbyOutcome::TooSlow => (
+                            None,
+                            ImportOutcome::Ignored {
+                                reason: "too slow for the leaderboard, archived".to_string(),
+                            },
+                        ),
+                        MainObbyOutcome::UnknownCategory => (
+                            None,
+                            ImportOutcome::Rejected {
+                                reason: "unknown category".to_string(),
+                            },
+                        ),
+                    };
+                }
+            }
+            report.items.push(applied);
+        }
+        self.commit();
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_sorts_entries_by_outcome() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Tower", "Bounce", "amy", Time::from_millis(10_000));
+        store.add_record_entry("Spiral", "Bounceless", "amy", Time::from_millis(30_000));
+
+        let report = store
+            .import_lua(
+                r#"{
+                    ["CTT2Mode"] = true,
+                    ["Tower"] = { ["Bounce"] = { "bob", 9.5 } },
+                    ["Spiral"] = { ["Bounceless"] = { "bob", 31 } },
+                    ["New"] = { ["Bounce"] = { "cat", 5 }, ["NoPlat"] = { "cat", 5 } },
+                    ["Broken"] = { ["Bounce"] = { "dan", "fast" }, ["Bounceless"] = 4 },
+                    ["Weird"] = 7,
+                    ["MainObby"] = { ["Bounce"] = { { "eve", 100 }, "junk" }, ["Speedrun"] = {} },
+                }"#,
+            )
+            .unwrap();
+
+        let targets = |items: Vec<&ImportItem>| -> Vec<String> {
+            items.into_iter().map(|i| i.target.clone()).collect()
+        };
+
+        assert_eq!(targets(report.added().collect()), ["New / Bounce", "Main Obby / Bounce"]);
+        assert_eq!(targets(report.improved().collect()), ["Tower / Bounce"]);
+        assert_eq!(targets(report.ignored().collect()), ["Spiral / Bounceless"]);
+        assert_eq!(report.rejected().count(), 6);
+        assert_eq!(report.summary(), "2 added, 1 improved, 1 ignored, 6 rejected");
+    }
+
+    #[test]
+    fn reimporting_is_ignored() {
+        let mut store = RecordStore::new();
+        let text = r#"{ ["Tower"] = { ["Bounce"] = { "amy", 10 } } }"#;
+
+        store.import_lua(text).unwrap();
+        let report = store.import_lua(text).unwrap();
+
+        assert_eq!(report.ignored().count(), 1);
+        assert_eq!(store.records().len(), 1);
+    }
+
+    #[test]
+    fn preview_changes_nothing_and_matches_the_import() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Tower", "Bounce", "amy", Time::from_millis(10_000));
+        let text = r#"{ ["Tower"] = { ["Bounce"] = { "bob", 9 }, ["Bounceless"] = { "bob", 20 } } }"#;
+
+        let preview = store.preview_import(text).unwrap();
+        assert_eq!(store.records().len(), 1);
+        assert_eq!(preview.improved().count(), 1);
+        assert_eq!(
+            preview.improved().next().unwrap().current,
+            Some(("amy".to_string(), Time::from_millis(10_000)))
+        );
+
+        let report = store.import_lua(text).unwrap();
+        assert_eq!(preview, report);
+    }
+
+    #[test]
+    fn applying_part_of_a_preview() {
+        let mut store = RecordStore::new();
+        let text = r#"{ ["Tower"] = { ["Bounce"] = { "bob", 9 }, ["Bounceless"] = { "bob", 20 } } }"#;
+
+        let preview = store.preview_import(text).unwrap();
+        let accepted = preview
+            .items
+            .iter()
+            .filter(|i| i.target == "Tower / Bounceless");
+        let report = store.apply_import(accepted);
+
+        assert_eq!(report.added().count(), 1);
+        assert_eq!(store.records().len(), 1);
+        assert_eq!(store.records()[0].mode, "Bounceless");
+        assert!(store.undo());
+        assert!(store.records().is_empty());
+    }
+
+    #[test]
+    fn reimporting_main_obby_changes_nothing() {
+        let mut store = RecordStore::new();
+        let text = r#"{ ["MainObby"] = { ["Bounce"] = { { "amy", 100 }, { "bob", 110 }, { "amy", 120 } } } }"#;
+
+        let first = store.import_lua(text).unwrap();
+        assert_eq!(first.added().count(), 2);
+        assert_eq!(first.ignored().count(), 1);
+
+        let revision = store.revision();
+        let second = store.import_lua(text).unwrap();
+        assert_eq!(second.ignored().count(), 3);
+        assert_eq!(store.revision(), revision);
+        assert_eq!(store.main_obby("Bounce").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn main_obby_categories_are_matched_by_name_or_tag() {
+        let mut store = RecordStore::new();
+        let mut categories = store.main_obby_categories().to_vec();
+        categories.push(MainObbyCategory::new("Speedrun", "SR", 5));
+        store.set_main_obby_categories(categories).unwrap();
+
+        let report = store
+            .import_lua(r#"{ ["MainObby"] = { ["SR"] = { { "amy", 50 } }, ["NT"] = { { "bob", 60 } }, ["XX"] = {} } }"#)
+            .unwrap();
+
+        assert_eq!(report.added().count(), 2);
+        assert_eq!(report.rejected().count(), 1);
+        assert_eq!(store.main_obby("Speedrun").unwrap(), &[("amy".to_string(), Time::from_millis(50_000))]);
+        assert_eq!(store.main_obby("NoPlat").unwrap(), &[("bob".to_string(), Time::from_millis(60_000))]);
+    }
+
+    #[test]
+    fn times_use_the_time_parser() {
+        let mut store = RecordStore::new();
+        let report = store
+            .import_lua(
+                r#"{
+                    ["Tower"] = { ["Bounce"] = { "amy", "1:23,456" } },
+                    ["Spiral"] = { ["Bounce"] = { "bob", -5 }, ["Bounceless"] = { "bob", (0/0) } },
+                    ["Maze"] = { ["Bounce"] = { "cat", math.huge } },
+                }"#,
+            )
+            .unwrap();
+
+        assert_eq!(report.added().count(), 1);
+        assert_eq!(report.rejected().count(), 3);
+        assert_eq!(store.records()[0].time, Time::from_millis(83_456));
+    }
+
+    #[test]
+    fn placeholder_entries_are_rejected() {
+        let mut store = RecordStore::new();
+        let report = store
+            .import_lua(
+                r#"{
+                    ["Tower"] = { ["Bounce"] = { "", 12 }, ["Bounceless"] = { "amy", 9999 } },
+                    ["MainObby"] = { ["Bounce"] = { { "  ", 100 }, { "bob", 9999 }, { "cat", 10800 } } },
+                }"#,
+            )
+            .unwrap();
+
+        assert_eq!(report.rejected().count(), 4);
+        assert!(store.records().is_empty());
+        // Three hours is a slow run, not the 9999s stand-in.
+        assert_eq!(store.main_obby("Bounce").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn obbies_without_a_usable_name_are_rejected() {
+        let mut store = RecordStore::new();
+        let report = store
+            .import_lua(
+                r#"{
+                    [""] = { ["Bounce"] = { "amy", 5 } },
+                    ["Boards"] = { ["  "] = { ["Bounce"] = { { "bob", 6 } } } },
+                }"#,
+            )
+            .unwrap();
+
+        let rejected: Vec<(&str, &str)> = report
+            .rejected()
+            .map(|i| match &i.outcome {
+                ImportOutcome::Rejected { reason } => (i.target.as_str(), reason.as_str()),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            rejected,
+            [
+                ("(unnamed)", "obby names cannot be empty"),
+                ("Boards / (unnamed)", "obby names cannot be empty")
+            ]
+        );
+        assert!(store.records().is_empty());
+
+        // A run filed under an obby called "Boards" by an older export.
+        let report = store
+            .import_lua(r#"{ ["Boards"] = { ["Bounceless"] = { "amy", 5 } } }"#)
+            .unwrap();
+        assert_eq!(report.rejected().count(), 2);
+        assert!(store.records().is_empty());
+    }
+
+    #[test]
+    fn obbies_named_like_export_settings_are_reported() {
+        let mut store = RecordStore::new();
+        store.set_ctt2_mode(true);
+        let report = store.import_lua(&store.generate_lua_export()).unwrap();
+        assert_eq!(report.rejected().count(), 0);
+
+        let report = store
+            .import_lua(
+                r#"{
+                    ["ModeTags"] = { ["Bounce"] = { "amy", 5 } },
+                    ["MainObbyTags"] = { ["Bounce"] = { "bob", 6 } },
+                    ["CTT2Mode"] = { ["Bounce"] = { "cat", 7 } },
+                }"#,
+            )
+            .unwrap();
+        let targets: Vec<&str> = report.rejected().map(|i| i.target.as_str()).collect();
+        assert_eq!(targets, ["ModeTags", "MainObbyTags", "CTT2Mode"]);
+        assert!(store.records().is_empty());
+    }
+
+    #[test]
+    fn bad_input_is_an_error_not_a_panic() {
+        let mut store = RecordStore::new();
+        assert!(store.import_lua("not lua at all").is_err());
+        assert!(store.import_lua("42").is_err());
+        assert!(store.import_lua("").is_err());
+        assert!(store.records().is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+//! Record model shared by the RecordAdder GUI, command line and HTTP server.
+
+mod export;
+mod history;
+mod import;
+pub mod luau;
+pub mod session;
+mod stats;
+mod store;
+mod time;
+
+pub use import::{ImportItem, ImportOutcome, ImportReport, ImportTarget};
+pub use stats::PlayerStats;
+pub use store::{
+    is_placeholder, obby_name_problem, username_problem, AddOutcome, Correction, MainObbyCategory, MainObbyOutcome, MainObbyRule,
+    ObbyIdentity, ObbyMode, Player, Record, RecordStore,
+};
+pub use time::{ParseTimeError, Time};
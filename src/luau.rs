@@ -0,0 +1,729 @@
+//! Reading and writing the Luau table literals exchanged with
+//! `RecordModule.add` and `RecordModule.get_records`.
+//!
+//! Clipboard text is never run as code. `parse` only understands table
+//! constructors, strings, numbers and booleans, and gives up on input that is
+//! too large or nested too deeply.
+
+use std::fmt::{self, Write};
+
+/// Largest clipboard text `parse` accepts.
+pub const MAX_INPUT_LEN: usize = 8 * 1024 * 1024;
+/// Deepest table nesting `parse` accepts. Record exports use three levels.
+pub const MAX_DEPTH: usize = 32;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Table(Table),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Key {
+    String(String),
+    Number(f64),
+    Bool(bool),
+}
+
+/// A Luau table with keyed and/or array entries, kept in source order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    entries: Vec<(Option<Key>, Value)>,
+}
+
+impl Table {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(&mut self, key: &str, value: Value) {
+        self.entries.push((Some(Key::String(key.to_string())), value));
+    }
+
+    pub fn push(&mut self, value: Value) {
+        self.entries.push((None, value));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The value stored under a string key. Later entries win, as in Luau.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| matches!(k, Some(Key::String(s)) if s == key))
+            .map(|(_, v)| v)
+    }
+
+    /// The value at a 1-based array index, either positional or `[n] = ...`.
+    pub fn index(&self, index: usize) -> Option<&Value> {
+        let explicit = self
+            .entries
+            .iter()
+            .rev()
+            .find(|(k, _)| matches!(k, Some(Key::Number(n)) if *n == index as f64));
+        if let Some((_, v)) = explicit {
+            return Some(v);
+        }
+
+        self.entries
+            .iter()
+            .filter(|(k, _)| k.is_none())
+            .nth(index.checked_sub(1)?)
+            .map(|(_, v)| v)
+    }
+
+    /// All keyed entries in source order.
+    pub fn pairs(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.entries
+            .iter()
+            .filter_map(|(k, v)| k.as_ref().map(|k| (k, v)))
+    }
+
+    /// The array part: positional entries in source order.
+    pub fn sequence(&self) -> impl Iterator<Item = &Value> {
+        self.entries
+            .iter()
+            .filter(|(k, _)| k.is_none())
+            .map(|(_, v)| v)
+    }
+
+    /// Arrays of plain values, like `{ "player", 12.345 }`, fit on one line.
+    fn is_inline(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|(key, value)| key.is_none() && !matches!(value, Value::Table(_)))
+    }
+
+    pub fn to_luau(&self) -> String {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        out
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        if self.entries.is_empty() {
+            out.push_str("{}");
+            return;
+        }
+
+        if self.is_inline() {
+            out.push_str("{ ");
+            for (i, (_, value)) in self.entries.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                value.write(out, depth);
+            }
+            out.push_str(" }");
+            return;
+        }
+
+        let indent = "  ".repeat(depth + 1);
+        out.push_str("{\n");
+        for (key, value) in &self.entries {
+            out.push_str(&indent);
+            if let Some(key) = key {
+                let _ = match key {
+                    Key::String(s) => write!(out, "[{}] = ", quote(s)),
+                    Key::Number(n) => write!(out, "[{}] = ", n),
+                    Key::Bool(b) => write!(out, "[{}] = ", b),
+                };
+            }
+            value.write(out, depth + 1);
+            out.push_str(",\n");
+        }
+        out.push_str(&"  ".repeat(depth));
+        out.push('}');
+    }
+}
+
+impl Value {
+    fn write(&self, out: &mut String, depth: usize) {
+        match self {
+            Value::Nil => out.push_str("nil"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => out.push_str(&number(*n)),
+            Value::String(s) => out.push_str(&quote(s)),
+            Value::Table(t) => t.write(out, depth),
+        }
+    }
+}
+
+/// Formats a time the way the leaderboards show it.
+pub fn number(n: f64) -> String {
+    if n.is_nan() {
+        "(0/0)".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "math.huge" } else { "-math.huge" }.to_string()
+    } else {
+        format!("{:.3}", n)
+    }
+}
+
+/// Quotes a string as a Luau string literal. Quotes, backslashes and control
+/// characters are escaped so no input can end the literal early.
+pub fn quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // Decimal escapes are padded to three digits so a following digit
+            // is never read as part of the escape.
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\{:03}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a single Luau value, optionally preceded by `return`.
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    if text.len() > MAX_INPUT_LEN {
+        return Err(ParseError {
+            message: format!(
+                "input is {} bytes, more than the limit of {} bytes",
+                text.len(),
+                MAX_INPUT_LEN
+            ),
+            line: 1,
+            column: 1,
+        });
+    }
+
+    let mut parser = Parser {
+        src: text.as_bytes(),
+        pos: 0,
+        depth: 0,
+    };
+
+    parser.skip_trivia()?;
+    if parser.peek_word() == Some("return") {
+        parser.pos += "return".len();
+    }
+
+    let value = parser.value()?;
+    parser.skip_trivia()?;
+    if parser.peek() == Some(b';') {
+        parser.pos += 1;
+        parser.skip_trivia()?;
+    }
+    if parser.pos < parser.src.len() {
+        return Err(parser.error("unexpected text after the table"));
+    }
+
+    Ok(value)
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        self.error_at(self.pos, message)
+    }
+
+    fn error_at(&self, pos: usize, message: &str) -> ParseError {
+        let before = &self.src[..pos.min(self.src.len())];
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let column = String::from_utf8_lossy(&before[line_start..]).chars().count() + 1;
+
+        ParseError {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        let rest = &self.src[self.pos..];
+        let len = rest
+            .iter()
+            .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+            .count();
+        if len == 0 || rest[0].is_ascii_digit() {
+            return None;
+        }
+        std::str::from_utf8(&rest[..len]).ok()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        self.skip_trivia()?;
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    /// Skips whitespace and `--` comments.
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'-') if self.peek_at(1) == Some(b'-') => {
+                    self.pos += 2;
+                    if self.peek() == Some(b'[')
+                        && let Some(level) = self.long_bracket_level()
+                    {
+                        self.long_string(level)?;
+                    } else {
+                        while !matches!(self.peek(), None | Some(b'\n')) {
+                            self.pos += 1;
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+
+        match self.peek() {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'{') => self.table().map(Value::Table),
+            Some(b'"') | Some(b'\'') => self.quoted_string().map(Value::String),
+            Some(b'[') => match self.long_bracket_level() {
+                Some(level) => {
+                    let bytes = self.long_string(level)?;
+                    Ok(Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+                }
+                None => Err(self.error("unexpected '['")),
+            },
+            Some(b'-') => {
+                // Counted in a loop rather than recursing, so a long run of
+                // signs cannot overflow the stack.
+                let mut signs = 0;
+                while self.peek() == Some(b'-') {
+                    signs += 1;
+                    if signs > MAX_DEPTH {
+                        return Err(self.error_at(start, &format!("more than {} '-' signs in a row", MAX_DEPTH)));
+                    }
+                    self.pos += 1;
+                    self.skip_trivia()?;
+                }
+                match self.value()? {
+                    Value::Number(n) if signs % 2 == 1 => Ok(Value::Number(-n)),
+                    Value::Number(n) => Ok(Value::Number(n)),
+                    _ => Err(self.error_at(start, "'-' must be followed by a number")),
+                }
+            }
+            Some(b'(') => {
+                // `(0/0)` is how NaN is written out.
+                self.pos += 1;
+                for expected in [b'0', b'/', b'0', b')'] {
+                    self.expect(expected)
+                        .map_err(|_| self.error_at(start, "only '(0/0)' is allowed in parentheses"))?;
+                }
+                Ok(Value::Number(f64::NAN))
+            }
+            Some(b) if b.is_ascii_digit() || b == b'.' => self.number().map(Value::Number),
+            Some(_) => match self.peek_word() {
+                Some("true") => {
+                    self.pos += 4;
+                    Ok(Value::Bool(true))
+                }
+                Some("false") => {
+                    self.pos += 5;
+                    Ok(Value::Bool(false))
+                }
+                Some("nil") => {
+                    self.pos += 3;
+                    Ok(Value::Nil)
+                }
+                Some("math") if self.src[self.pos..].starts_with(b"math.huge") => {
+                    self.pos += "math.huge".len();
+                    Ok(Value::Number(f64::INFINITY))
+                }
+                Some(word) => Err(self.error(&format!(
+                    "'{}' is not allowed here, only tables, strings, numbers and booleans are",
+                    word
+                ))),
+                None => Err(self.error("unexpected character")),
+            },
+        }
+    }
+
+    fn table(&mut self) -> Result<Table, ParseError> {
+        let open = self.pos;
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error_at(
+                open,
+                &format!("tables are nested more than {} levels deep", MAX_DEPTH),
+            ));
+        }
+        self.pos += 1;
+
+        let mut table = Table::new();
+        loop {
+            self.skip_trivia()?;
+            match self.peek() {
+                None => return Err(self.error_at(open, "table is never closed")),
+                Some(b'}') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+
+            let key = self.table_key()?;
+            let value = self.value()?;
+            table.entries.push((key, value));
+
+            self.skip_trivia()?;
+            match self.peek() {
+                Some(b',') | Some(b';') => self.pos += 1,
+                Some(b'}') => {}
+                None => return Err(self.error_at(open, "table is never closed")),
+                Some(_) => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+
+        self.depth -= 1;
+        Ok(table)
+    }
+
+    /// Reads `[key] =` or `name =` if present.
+    fn table_key(&mut self) -> Result<Option<Key>, ParseError> {
+        let start = self.pos;
+
+        if self.peek() == Some(b'[') && self.long_bracket_level().is_none() {
+            self.pos += 1;
+            let key = match self.value()? {
+                Value::String(s) => Key::String(s),
+                Value::Number(n) if !n.is_nan() => Key::Number(n),
+                Value::Bool(b) => Key::Bool(b),
+                _ => return Err(self.error_at(start, "table keys must be strings, numbers or booleans")),
+            };
+            self.expect(b']')?;
+            self.expect(b'=')?;
+            return Ok(Some(key));
+        }
+
+        if let Some(word) = self.peek_word()
+            && !matches!(word, "true" | "false" | "nil" | "math")
+        {
+            let name = word.to_string();
+            self.pos += name.len();
+            self.expect(b'=')?;
+            return Ok(Some(Key::String(name)));
+        }
+
+        Ok(None)
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let start = self.pos;
+        let rest = &self.src[self.pos..];
+
+        if rest.starts_with(b"0x") || rest.starts_with(b"0X") {
+            let digits = rest[2..]
+                .iter()
+                .take_while(|b| b.is_ascii_hexdigit() || **b == b'_')
+                .count();
+            let text: String = String::from_utf8_lossy(&rest[2..2 + digits]).replace('_', "");
+            self.pos += 2 + digits;
+            return u64::from_str_radix(&text, 16)
+                .map(|n| n as f64)
+                .map_err(|_| self.error_at(start, "invalid hexadecimal number"));
+        }
+
+        let mut len = 0;
+        while let Some(&b) = rest.get(len) {
+            let exponent_sign =
+                (b == b'+' || b == b'-') && len > 0 && matches!(rest[len - 1], b'e' | b'E');
+            if b.is_ascii_digit() || b == b'.' || b == b'_' || b == b'e' || b == b'E' || exponent_sign {
+                len += 1;
+            } else {
+                break;
+            }
+        }
+
+        let text = String::from_utf8_lossy(&rest[..len]).replace('_', "");
+        self.pos += len;
+        text.parse::<f64>()
+            .map_err(|_| self.error_at(start, &format!("'{}' is not a valid number", text)))
+    }
+
+    fn quoted_string(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let quote = self.src[self.pos];
+        self.pos += 1;
+        let mut bytes = Vec::new();
+
+        loop {
+            let Some(b) = self.peek() else {
+                return Err(self.error_at(start, "string is never closed"));
+            };
+            self.pos += 1;
+
+            match b {
+                b if b == quote => break,
+                b'\n' => return Err(self.error_at(start, "string is never closed")),
+                b'\\' => self.escape(&mut bytes)?,
+                b => bytes.push(b),
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn escape(&mut self, bytes: &mut Vec<u8>) -> Result<(), ParseError> {
+        let start = self.pos - 1;
+        let Some(b) = self.peek() else {
+            return Err(self.error_at(start, "string is never closed"));
+        };
+        self.pos += 1;
+
+        match b {
+            b'n' => bytes.push(b'\n'),
+            b't' => bytes.push(b'\t'),
+            b'r' => bytes.push(b'\r'),
+            b'a' => bytes.push(0x07),
+            b'b' => bytes.push(0x08),
+            b'f' => bytes.push(0x0c),
+            b'v' => bytes.push(0x0b),
+            b'\\' | b'"' | b'\'' => bytes.push(b),
+            // `string.format("%q")` writes newlines as a backslash followed by
+            // a real line break.
+            b'\n' => bytes.push(b'\n'),
+            b'\r' => {
+                if self.peek() == Some(b'\n') {
+                    self.pos += 1;
+                }
+                bytes.push(b'\n');
+            }
+            b'z' => {
+                while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+                    self.pos += 1;
+                }
+            }
+            b'x' => {
+                let hex = self.src.get(self.pos..self.pos + 2).unwrap_or_default();
+                let value = std::str::from_utf8(hex)
+                    .ok()
+                    .filter(|h| h.len() == 2)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| self.error_at(start, "'\\x' needs two hexadecimal digits"))?;
+                self.pos += 2;
+                bytes.push(value);
+            }
+            b'u' => {
+                if self.peek() != Some(b'{') {
+                    return Err(self.error_at(start, "'\\u' needs a code point in braces"));
+                }
+                let close = self.src[self.pos..]
+                    .iter()
+                    .position(|&b| b == b'}')
+                    .ok_or_else(|| self.error_at(start, "'\\u{' is never closed"))?;
+                let hex = String::from_utf8_lossy(&self.src[self.pos + 1..self.pos + close]).into_owned();
+                let c = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error_at(start, "invalid '\\u' code point"))?;
+                self.pos += close + 1;
+                bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            b if b.is_ascii_digit() => {
+                let mut value = (b - b'0') as u32;
+                for _ in 0..2 {
+                    match self.peek() {
+                        Some(d) if d.is_ascii_digit() => {
+                            value = value * 10 + (d - b'0') as u32;
+                            self.pos += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                let value = u8::try_from(value)
+                    .map_err(|_| self.error_at(start, "decimal escape is larger than 255"))?;
+                bytes.push(value);
+            }
+            _ => return Err(self.error_at(start, "invalid escape sequence")),
+        }
+
+        Ok(())
+    }
+
+    /// If a long bracket `[[` or `[==[` starts here, returns its level.
+    fn long_bracket_level(&self) -> Option<usize> {
+        let rest = &self.src[self.pos..];
+        if rest.first() != Some(&b'[') {
+            return None;
+        }
+        let level = rest[1..].iter().take_while(|&&b| b == b'=').count();
+        (rest.get(1 + level) == Some(&b'[')).then_some(level)
+    }
+
+    fn long_string(&mut self, level: usize) -> Result<Vec<u8>, ParseError> {
+        let start = self.pos;
+        self.pos += level + 2;
+        // A line break right after the opening bracket is not part of the string.
+        if self.peek() == Some(b'\r') {
+            self.pos += 1;
+        }
+        if self.peek() == Some(b'\n') {
+            self.pos += 1;
+        }
+
+        let mut close = vec![b']'];
+        close.extend(std::iter::repeat_n(b'=', level));
+        close.push(b']');
+
+        let rest = &self.src[self.pos..];
+        let end = rest
+            .windows(close.len())
+            .position(|w| w == close.as_slice())
+            .ok_or_else(|| self.error_at(start, "long string is never closed"))?;
+        let bytes = rest[..end].to_vec();
+        self.pos += end + close.len();
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_escapes_everything_that_could_end_the_string() {
+        assert_eq!(quote("plain"), "\"plain\"");
+        assert_eq!(quote("a\"b"), "\"a\\\"b\"");
+        assert_eq!(quote("a\\b"), "\"a\\\\b\"");
+        assert_eq!(quote("a\nb"), "\"a\\nb\"");
+        assert_eq!(quote("a\u{0}1"), "\"a\\0001\"");
+    }
+
+    #[test]
+    fn tables_render_like_the_clipboard_format() {
+        let mut record = Table::new();
+        record.push(Value::String("amy".to_string()));
+        record.push(Value::Number(12.5));
+
+        let mut modes = Table::new();
+        modes.field("Bounce", Value::Table(record));
+
+        let mut root = Table::new();
+        root.field("CTT2Mode", Value::Bool(false));
+        root.field("Lava Tower", Value::Table(modes));
+
+        assert_eq!(
+            root.to_luau(),
+            "{\n  [\"CTT2Mode\"] = false,\n  [\"Lava Tower\"] = {\n    [\"Bounce\"] = { \"amy\", 12.500 },\n  },\n}"
+        );
+        assert_eq!(parse(&root.to_luau()), Ok(Value::Table(root)));
+    }
+
+    #[test]
+    fn parses_record_module_output() {
+        let text = "return {\n  [\"CTT2Mode\"] = true, -- comment\n  Tower = { Bounce = { 'amy', 1.5e1 }; },\n  [\"MainObby\"] = { [\"NoPlat\"] = { { \"bob\\\n\", -0x10 } } },\n}";
+        let Value::Table(root) = parse(text).unwrap() else {
+            panic!("expected a table");
+        };
+
+        assert_eq!(root.get("CTT2Mode"), Some(&Value::Bool(true)));
+
+        let Some(Value::Table(tower)) = root.get("Tower") else {
+            panic!("expected Tower");
+        };
+        let Some(Value::Table(bounce)) = tower.get("Bounce") else {
+            panic!("expected Bounce");
+        };
+        assert_eq!(bounce.index(1), Some(&Value::String("amy".to_string())));
+        assert_eq!(bounce.index(2), Some(&Value::Number(15.0)));
+
+        let Some(Value::Table(main)) = root.get("MainObby") else {
+            panic!("expected MainObby");
+        };
+        let Some(Value::Table(noplat)) = main.get("NoPlat") else {
+            panic!("expected NoPlat");
+        };
+        let Some(Value::Table(entry)) = noplat.index(1) else {
+            panic!("expected an entry");
+        };
+        assert_eq!(entry.index(1), Some(&Value::String("bob\n".to_string())));
+        assert_eq!(entry.index(2), Some(&Value::Number(-16.0)));
+    }
+
+    #[test]
+    fn rejects_code() {
+        for text in [
+            "while true do end",
+            "{ os.exit() }",
+            "{ [\"a\"] = print }",
+            "(function() end)()",
+            "{ 1 } os.exit()",
+            "{ a = 1 .. 2 }",
+        ] {
+            assert!(parse(text).is_err(), "{:?} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn reports_limits_and_positions() {
+        let deep = "{".repeat(MAX_DEPTH + 1) + &"}".repeat(MAX_DEPTH + 1);
+        let err = parse(&deep).unwrap_err();
+        assert!(err.message.contains("nested"), "{}", err);
+
+        let signs = format!("{{ a = {}1 }}", "- ".repeat(5000));
+        let err = parse(&signs).unwrap_err();
+        assert!(err.message.contains("'-' signs"), "{}", err);
+        assert_eq!(parse("{ a = - -1 }").unwrap(), parse("{ a = 1 }").unwrap());
+
+        let huge = " ".repeat(MAX_INPUT_LEN + 1);
+        let err = parse(&huge).unwrap_err();
+        assert!(err.message.contains("limit"), "{}", err);
+
+        let err = parse("{\n  a = 1,\n  b = \"open\n}").unwrap_err();
+        assert_eq!((err.line, err.column), (3, 7));
+    }
+}
@@ -1,332 +1,1278 @@
+mod cli;
+
 use arboard::Clipboard;
 use eframe::egui;
-use mlua::Lua;
+use recordadder::{
+    is_placeholder, session, username_problem, AddOutcome, ImportItem, ImportOutcome, ImportReport, MainObbyCategory, MainObbyRule, ObbyMode,
+    Record, RecordStore, Time,
+};
 use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tiny_http::{Server, Response};
-use serde::Serialize;
 
-#[derive(Clone)]
-struct Record {
-    player: String,
-    time: f32,
-    bounce: bool,
-    obby: String,
-}
-
-#[derive(Serialize)]
-struct ExportTable {
-    CTT2Mode: bool,
-    #[serde(flatten)]
-    obbies: HashMap<String, HashMap<String, (String, f32)>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    MainObby: Option<HashMap<String, Vec<(String, f32)>>>,
-}
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+const HTTP_ADDR: &str = "127.0.0.1:14855";
 
 struct AppState {
+    store: RecordStore,
+
     player_input: String,
     time_input: String,
     obby_input: String,
-    is_bounce: bool,
-    ctt2_mode: bool,
-    records: Vec<Record>,
+    mode_input: String,
+    /// What happened to the last run added with "Add Record", if worth saying.
+    add_record_message: Option<String>,
     show_help: bool,
+    show_matrix: bool,
+    show_stats: bool,
+    matrix_gaps_only: bool,
 
     main_player_input: String,
     main_time_input: String,
     main_category: String,
 
-    main_ob_bounce: Vec<(String, f32)>,
-    main_ob_bounceless: Vec<(String, f32)>,
-    main_ob_noplat: Vec<(String, f32)>,
-    obby_names: HashSet<String>,
-
     real_time_enabled: bool,
     http_thread: Option<thread::JoinHandle<()>>,
     http_data: Arc<Mutex<String>>,
+    published_revision: u64,
+
+    session_path_input: String,
+    session_status: String,
+    autosaved_revision: u64,
+    last_autosave: Instant,
+    recovery_prompt: bool,
+
+    history_view_obby: Option<String>,
+    /// Board size being dragged or typed in the History window. It is stored
+    /// once the field is let go, so one drag is one undo step.
+    board_size_draft: Option<(String, usize)>,
+    import_preview: Option<(ImportReport, Vec<bool>)>,
+    import_result: Option<Result<ImportReport, String>>,
+    category_settings: Option<CategoryDraft>,
+    entry_editor: Option<EntryEditor>,
+    obby_manager: Option<ObbyManager>,
+    player_manager: Option<PlayerManager>,
+    /// New player names kept despite breaking the Roblox username rules.
+    approved_names: HashSet<String>,
+
+    record_filter: String,
+    record_mode_filter: Option<String>,
+    record_sort: RecordColumn,
+    record_sort_ascending: bool,
+}
+
+/// The obby picked in the Obbies window and its pending edits.
+#[derive(Default)]
+struct ObbyManager {
+    selected: Option<String>,
+    new_name: String,
+    display_name: String,
+    merge_into: String,
+    error: String,
+}
+
+/// The player picked in the Players window and their pending edits. Aliases
+/// are edited as one comma-separated line.
+#[derive(Default)]
+struct PlayerManager {
+    selected: Option<String>,
+    new_name: String,
+    aliases: String,
+    roblox_id: String,
+    merge_into: String,
+    error: String,
+}
+
+/// A column of the records table.
+#[derive(Clone, Copy, PartialEq)]
+enum RecordColumn {
+    Obby,
+    Mode,
+    Player,
+    Time,
+}
+
+impl RecordColumn {
+    /// Every column with its title and width.
+    const ALL: [(RecordColumn, &'static str, f32); 4] = [
+        (RecordColumn::Obby, "Obby", 180.0),
+        (RecordColumn::Mode, "Mode", 90.0),
+        (RecordColumn::Player, "Player", 150.0),
+        (RecordColumn::Time, "Time", 90.0),
+    ];
+}
+
+/// Modes and Main Obby categories being edited in the Categories window. The
+/// flag marks rows that already exist; their name cannot be changed.
+struct CategoryDraft {
+    modes: Vec<(bool, ObbyMode)>,
+    main_obby: Vec<(bool, MainObbyCategory)>,
+    error: String,
+}
+
+/// Which entry the Edit window changes.
+enum EditTarget {
+    Run(usize),
+    MainObby { category: String, place: usize },
+}
+
+/// Fields of the Edit window. Obby and mode only apply to runs.
+struct EntryEditor {
+    target: EditTarget,
+    /// Store revision the entry was read at. Any later change (an undo, a
+    /// delete, a new run) can move the entry, so the window closes then.
+    revision: u64,
+    player: String,
+    time: String,
+    obby: String,
+    mode: String,
+    reason: String,
+    error: String,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
+            store: RecordStore::new(),
+
             player_input: String::new(),
             time_input: String::new(),
             obby_input: String::new(),
-            is_bounce: false,
-            ctt2_mode: false,
-            records: Vec::new(),
+            // Filled in with the first configured mode and category.
+            mode_input: String::new(),
+            add_record_message: None,
             show_help: false,
+            show_matrix: false,
+            show_stats: false,
+            matrix_gaps_only: false,
 
             main_player_input: String::new(),
             main_time_input: String::new(),
-            main_category: "Bounce".to_string(),
-
-            main_ob_bounce: Vec::new(),
-            main_ob_bounceless: Vec::new(),
-            main_ob_noplat: Vec::new(),
-            obby_names: HashSet::new(),
+            main_category: String::new(),
 
             real_time_enabled: false,
             http_thread: None,
             http_data: Arc::new(Mutex::new("{}".to_string())),
+            published_revision: 0,
+
+            session_path_input: "records.json".to_string(),
+            session_status: String::new(),
+            autosaved_revision: 0,
+            last_autosave: Instant::now(),
+            recovery_prompt: false,
+
+            history_view_obby: None,
+            board_size_draft: None,
+            import_preview: None,
+            import_result: None,
+            category_settings: None,
+            entry_editor: None,
+            obby_manager: None,
+            player_manager: None,
+            approved_names: HashSet::new(),
+
+            record_filter: String::new(),
+            record_mode_filter: None,
+            record_sort: RecordColumn::Obby,
+            record_sort_ascending: true,
         }
     }
 }
 
 impl AppState {
-    fn add_record_entry(&mut self, obby: &str, bounce: bool, player: &str, time: f32) {
-        let new_record = Record {
-            player: player.to_string(),
-            time,
-            bounce,
-            obby: obby.to_string(),
-        };
-    
-        self.obby_names.insert(obby.to_string()); // track it
-    
-        if let Some(existing_index) = self
-            .records
-            .iter()
-            .position(|r| r.obby == new_record.obby && r.bounce == new_record.bounce)
+    fn refresh_http_data(&mut self) {
+        self.published_revision = self.store.revision();
+        if self.real_time_enabled
+            && let Ok(mut data) = self.http_data.lock()
         {
-            if self.records[existing_index].time > new_record.time {
-                self.records[existing_index] = new_record;
-            }
-        } else {
-            self.records.push(new_record);
+            *data = self.store.generate_json_export();
         }
     }
 
-    fn generate_json_export(&self) -> String {
-        let mut obbies: HashMap<String, HashMap<String, (String, f32)>> = HashMap::new();
-    
-        for r in &self.records {
-            let bounce_type = if r.bounce { "Bounce" } else { "Bounceless" };
-            obbies
-                .entry(r.obby.clone())
-                .or_default()
-                .insert(bounce_type.to_string(), (r.player.clone(), r.time));
-        }
-    
-        let main_obby = if self.ctt2_mode {
-            let mut mo = HashMap::new();
-            if !self.main_ob_bounce.is_empty() {
-                mo.insert("Bounce".to_string(), self.main_ob_bounce.clone());
-            }
-            if !self.main_ob_bounceless.is_empty() {
-                mo.insert("Bounceless".to_string(), self.main_ob_bounceless.clone());
-            }
-            if !self.main_ob_noplat.is_empty() {
-                mo.insert("NoPlat".to_string(), self.main_ob_noplat.clone());
-            }
-            Some(mo)
-        } else {
-            None
+    fn open_category_settings(&mut self) {
+        self.category_settings = Some(CategoryDraft {
+            modes: self.store.modes().iter().map(|m| (true, m.clone())).collect(),
+            main_obby: self
+                .store
+                .main_obby_categories()
+                .iter()
+                .map(|c| (true, c.clone()))
+                .collect(),
+            error: String::new(),
+        });
+    }
+
+    fn edit_run(&mut self, index: usize) {
+        let run = &self.store.records()[index];
+        self.entry_editor = Some(EntryEditor {
+            target: EditTarget::Run(index),
+            player: run.player.clone(),
+            time: run.time.to_string(),
+            obby: run.obby.clone(),
+            mode: run.mode.clone(),
+            reason: String::new(),
+            error: String::new(),
+            revision: self.store.revision(),
+        });
+    }
+
+    fn edit_main_obby_entry(&mut self, category: &str, place: usize) {
+        let (Some(board), Some(archive)) = (self.store.main_obby(category), self.store.main_obby_archive(category))
+        else {
+            return;
         };
-    
-        let export = ExportTable {
-            CTT2Mode: self.ctt2_mode,
-            obbies,
-            MainObby: main_obby,
+        let Some((player, time)) = board.iter().chain(archive).nth(place) else {
+            return;
         };
-    
-        serde_json::to_string(&export).unwrap_or_else(|_| "{}".to_string())
-    }    
+        self.entry_editor = Some(EntryEditor {
+            target: EditTarget::MainObby {
+                category: category.to_string(),
+                place,
+            },
+            player: player.clone(),
+            time: time.to_string(),
+            obby: String::new(),
+            mode: String::new(),
+            reason: String::new(),
+            error: String::new(),
+            revision: self.store.revision(),
+        });
+    }
+
+    /// The current records that pass the table filters, in table order.
+    fn table_rows(&self) -> Vec<usize> {
+        let records = self.store.records();
+        let filter = self.record_filter.trim().to_lowercase();
+        let mut rows: Vec<usize> = self
+            .store
+            .current_record_indices()
+            .into_iter()
+            .filter(|&i| {
+                let record = &records[i];
+                self.record_mode_filter.as_ref().is_none_or(|mode| *mode == record.mode)
+                    && (filter.is_empty()
+                        || record.obby.to_lowercase().contains(&filter)
+                        || self.store.display_name(&record.obby).to_lowercase().contains(&filter)
+                        || record.player.to_lowercase().contains(&filter))
+            })
+            .collect();
+
+        let obby_key = |i: usize| self.store.display_name(&records[i].obby).to_lowercase();
+        match self.record_sort {
+            RecordColumn::Time => rows.sort_by_cached_key(|&i| (records[i].time, obby_key(i))),
+            column => rows.sort_by_cached_key(|&i| {
+                let record = &records[i];
+                let key = match column {
+                    RecordColumn::Mode => record.mode.to_lowercase(),
+                    RecordColumn::Player => record.player.to_lowercase(),
+                    _ => obby_key(i),
+                };
+                (key, obby_key(i))
+            }),
+        }
+        if !self.record_sort_ascending {
+            rows.reverse();
+        }
+        rows
+    }
 
     fn add_record(&mut self) {
         let obby = self.obby_input.trim().to_string();
         let player = self.player_input.trim().to_string();
-        let bounce = self.is_bounce;
     
-        if obby.is_empty() || player.is_empty() {
+        if player.is_empty() {
             return;
         }
     
-        if let Ok(time) = self.time_input.parse::<f32>() {
-            self.add_record_entry(&obby, bounce, &player, time);
-            self.obby_input = obby.clone();
-            self.obby_names.insert(obby);
-            self.player_input.clear();
-            self.time_input.clear();
-            self.obby_input.clear();
-            self.is_bounce = false;
+        if let Ok(time) = self.time_input.parse::<Time>() {
+            self.store.begin("Add record");
+            let outcome = self.store.add_record_entry(&obby, &self.mode_input, &player, time);
+            self.store.commit();
+            let (message, done) = match outcome {
+                AddOutcome::Added | AddOutcome::Improved(_) => (None, true),
+                AddOutcome::Slower(holder) => (
+                    Some(format!(
+                        "Kept in the history, but {} holds the record with {}s.",
+                        holder.player, holder.time
+                    )),
+                    true,
+                ),
+                AddOutcome::Duplicate => (Some("That run is already recorded.".to_string()), true),
+                AddOutcome::UnknownMode => (Some(format!("Not added: unknown mode '{}'.", self.mode_input)), false),
+                AddOutcome::BadObbyName(problem) => (Some(format!("Not added: {}.", problem)), false),
+            };
+            self.add_record_message = message;
+            if done {
+                self.player_input.clear();
+                self.time_input.clear();
+                self.obby_input.clear();
+            }
+        }
+    }
+
+    /// Points the mode and category pickers at existing ones again after the
+    /// configuration changed, e.g. through Categories, undo or a loaded session.
+    fn keep_selections_valid(&mut self) {
+        if self.store.mode(&self.mode_input).is_none() {
+            self.mode_input = self.store.modes().first().map(|m| m.name.clone()).unwrap_or_default();
         }
+        if self.store.main_obby_category(&self.main_category).is_none() {
+            self.main_category = self
+                .store
+                .main_obby_categories()
+                .first()
+                .map(|c| c.name.clone())
+                .unwrap_or_default();
+        }
+    }
 
-        if self.real_time_enabled {
-            if let Ok(mut data) = self.http_data.lock() {
-                *data = self.generate_json_export();
+    fn import_from_clipboard(&mut self) {
+        let content = match Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(content) => content,
+            Err(e) => {
+                self.import_result = Some(Err(format!("Could not read text from the clipboard: {}", e)));
+                return;
             }
-        }        
-    }    
-    fn add_main_ob_record(&mut self, player: String, time: f32, category: &str) {
-        let list = match category {
-            "Bounce" => &mut self.main_ob_bounce,
-            "Bounceless" => &mut self.main_ob_bounceless,
-            "NoPlat" => &mut self.main_ob_noplat,
-            _ => return,
         };
+        match self.store.preview_import(&content) {
+            Ok(preview) => {
+                // Only pre-select entries that actually change something.
+                let accepted = preview
+                    .items
+                    .iter()
+                    .map(|i| matches!(i.outcome, ImportOutcome::Added | ImportOutcome::Improved))
+                    .collect();
+                self.import_preview = Some((preview, accepted));
+            }
+            Err(e) => {
+                self.import_result = Some(Err(format!("Could not import the clipboard: {}", e)));
+            }
+        }
+    }
+
+    /// Applies the approved rows of the open import preview.
+    fn apply_import_preview(&mut self, all: bool) {
+        if let Some((preview, accepted)) = self.import_preview.take() {
+            let items = preview
+                .items
+                .iter()
+                .zip(&accepted)
+                .filter(|(item, accepted)| item.destination.is_some() && (all || **accepted))
+                .map(|(item, _)| item);
+            self.import_result = Some(Ok(self.store.apply_import(items)));
+        }
+    }
+
+    fn copy_to_clipboard(&mut self) {
+        let output = self.store.generate_lua_export();
+        self.refresh_http_data();
+
+        if let Ok(mut clipboard) = Clipboard::new() {
+            clipboard.set_text(output).ok();
+        }
+    }
 
-        list.push((player, time));
-        list.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    /// Replaces the current store, e.g. after opening a session file.
+    fn replace_store(&mut self, store: RecordStore) {
+        self.store = store;
+        // A fresh store starts counting at zero again, so force a save and a
+        // republish on the next frame.
+        self.autosaved_revision = u64::MAX;
+        self.refresh_http_data();
+    }
 
-        let max_len = match category {
-            "Bounce" => 12,
-            "Bounceless" => 11,
-            "NoPlat" => 10,
-            _ => 0,
+    fn save_session(&mut self) {
+        let path = PathBuf::from(self.session_path_input.trim());
+        self.session_status = match session::save(&path, &self.store) {
+            Ok(()) => format!("Saved to {}", path.display()),
+            Err(e) => format!("Could not save {}: {}", path.display(), e),
         };
+    }
 
-        if list.len() > max_len {
-            list.truncate(max_len);
+    fn open_session(&mut self) {
+        let path = PathBuf::from(self.session_path_input.trim());
+        match session::load(&path) {
+            Ok(loaded) => {
+                self.replace_store(loaded);
+                self.session_status = format!("Opened {}", path.display());
+            }
+            Err(e) => {
+                self.session_status = format!("Could not open {}: {}", path.display(), e);
+            }
         }
+    }
 
-        if self.real_time_enabled {
-            if let Ok(mut data) = self.http_data.lock() {
-                *data = self.generate_json_export();
+    fn autosave(&mut self) {
+        self.last_autosave = Instant::now();
+        if session::save(&session::autosave_path(), &self.store).is_ok() {
+            self.autosaved_revision = self.store.revision();
+        }
+    }
+
+    fn recover_session(&mut self) {
+        match session::load(&session::autosave_path()) {
+            Ok(recovered) => {
+                self.replace_store(recovered);
+                self.session_status = "Recovered the previous session.".to_string();
+            }
+            Err(e) => {
+                self.session_status = format!("Could not recover the previous session: {}", e);
             }
-        }        
+        }
+        self.recovery_prompt = false;
     }
+}
 
-    fn import_from_clipboard(&mut self) {
-        if let Ok(mut clipboard) = Clipboard::new() {
-            if let Ok(content) = clipboard.get_text() {
-                let lua = Lua::new();
-                let result: mlua::Result<mlua::Table> =
-                    lua.load(&format!("return {}", content)).eval();
-
-                if let Ok(table) = result {
-                    for pair in table.pairs::<mlua::Value, mlua::Value>() {
-                        if let Ok((key, value)) = pair {
-                            if let mlua::Value::String(key_str) = &key {
-                                if key_str.to_str().ok() == Some("CTT2Mode") {
-                                    continue;
+impl eframe::App for AppState {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Don't overwrite the autosave until the user has decided whether to recover it.
+        if self.recovery_prompt {
+            egui::Window::new("Recover Session")
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label("The last session did not shut down cleanly.");
+                    ui.label("Do you want to recover the autosaved records?");
+                    ui.horizontal(|ui| {
+                        if ui.button("Recover").clicked() {
+                            self.recover_session();
+                        }
+                        if ui.button("Discard").clicked() {
+                            self.recovery_prompt = false;
+                        }
+                    });
+                });
+        } else if self.store.revision() != self.autosaved_revision
+            && self.last_autosave.elapsed() >= AUTOSAVE_INTERVAL
+        {
+            self.autosave();
+        }
+        ctx.request_repaint_after(AUTOSAVE_INTERVAL);
+        self.keep_selections_valid();
+
+        // Text fields keep their own undo, so only take the shortcuts when none has focus.
+        if ctx.memory(|m| m.focus().is_none()) {
+            let redo = egui::KeyboardShortcut::new(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::Z,
+            );
+            let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+
+            if ctx.input_mut(|i| i.consume_shortcut(&redo)) {
+                self.store.redo();
+            } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+                self.store.undo();
+            }
+        }
+
+        if let Some(obby) = self.history_view_obby.clone() {
+            let mut open = true;
+            let mut to_delete: Option<usize> = None;
+            let mut to_edit: Option<usize> = None;
+            let draft = self.board_size_draft.take().filter(|(o, _)| *o == obby).map(|(_, n)| n);
+            let mut board_size = draft.or(self.store.board_size(&obby));
+            let mut editing_size = false;
+
+            egui::Window::new(format!("History - {}", self.store.display_name(&obby)))
+                .id(egui::Id::new("history_window"))
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        let mut has_board = board_size.is_some();
+                        if ui.checkbox(&mut has_board, "Top-N board").changed() {
+                            board_size = has_board.then_some(10);
+                        }
+                        if let Some(size) = &mut board_size {
+                            let response = ui.add(egui::DragValue::new(size).clamp_range(1..=100));
+                            editing_size = response.dragged() || response.has_focus();
+                            ui.label("places");
+                        }
+                    });
+                    ui.separator();
+
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for mode in self.store.modes() {
+                            let runs = self.store.runs_for(&obby, &mode.name);
+                            if runs.is_empty() {
+                                continue;
+                            }
+
+                            ui.heading(&mode.name);
+                            for (rank, i) in runs.into_iter().enumerate() {
+                                let run = &self.store.records()[i];
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "{}. {} - {}s{}{}",
+                                        rank + 1,
+                                        run.player,
+                                        run.time,
+                                        if rank == 0 { " (WR)" } else { "" },
+                                        placeholder_mark(run.is_placeholder())
+                                    ));
+                                    if ui.button("Edit").clicked() {
+                                        to_edit = Some(i);
+                                    }
+                                    if ui.button("Delete").clicked() {
+                                        to_delete = Some(i);
+                                    }
+                                });
+                            }
+                        }
+                    });
+                });
+
+            if let Some(i) = to_edit {
+                self.edit_run(i);
+            }
+            if let Some(i) = to_delete {
+                self.store.delete_record(i);
+            }
+            match board_size {
+                Some(size) if editing_size && open => self.board_size_draft = Some((obby.clone(), size)),
+                _ => {
+                    // The obby may have been renamed or removed meanwhile.
+                    let _ = self.store.set_board_size(&obby, board_size);
+                }
+            }
+            if !open {
+                self.history_view_obby = None;
+            }
+        }
+
+        if let Some((preview, accepted)) = &mut self.import_preview {
+            let mut open = true;
+            let mut apply: Option<bool> = None;
+            let mut cancel = false;
+
+            egui::Window::new("Import Preview")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label(format!("Would result in: {}", preview.summary()));
+                    ui.horizontal(|ui| {
+                        if ui.button("Select All").clicked() {
+                            for (item, accepted) in preview.items.iter().zip(accepted.iter_mut()) {
+                                *accepted = item.destination.is_some();
+                            }
+                        }
+                        if ui.button("Select None").clicked() {
+                            accepted.iter_mut().for_each(|a| *a = false);
+                        }
+                    });
+                    ui.separator();
+
+                    egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                        egui::Grid::new("import_preview")
+                            .striped(true)
+                            .num_columns(5)
+                            .show(ui, |ui| {
+                                ui.strong("");
+                                ui.strong("Obby / Mode");
+                                ui.strong("Current");
+                                ui.strong("Incoming");
+                                ui.strong("Action");
+                                ui.end_row();
+
+                                for (item, accepted) in preview.items.iter().zip(accepted.iter_mut()) {
+                                    ui.add_enabled(item.destination.is_some(), egui::Checkbox::without_text(accepted));
+                                    ui.label(&item.target);
+                                    ui.label(match &item.current {
+                                        Some((p, t)) => format!("{} - {}s", p, t),
+                                        None => "-".to_string(),
+                                    });
+                                    ui.label(match (&item.player, item.time) {
+                                        (Some(p), Some(t)) => format!("{} - {}s", p, t),
+                                        _ => "-".to_string(),
+                                    });
+                                    if matches!(item.outcome, ImportOutcome::Rejected { .. }) {
+                                        ui.colored_label(ui.visuals().error_fg_color, item.action());
+                                    } else {
+                                        ui.label(item.action());
+                                    }
+                                    ui.end_row();
                                 }
+                            });
+                    });
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply Selected").clicked() {
+                            apply = Some(false);
+                        }
+                        if ui.button("Apply All").clicked() {
+                            apply = Some(true);
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if let Some(all) = apply {
+                self.apply_import_preview(all);
+            } else if cancel || !open {
+                self.import_preview = None;
+            }
+        }
+
+        if let Some(result) = &self.import_result {
+            let mut open = true;
+
+            egui::Window::new("Import Report")
+                .open(&mut open)
+                .show(ctx, |ui| match result {
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                    Ok(report) => {
+                        ui.label(report.summary());
+                        egui::ScrollArea::vertical().show(ui, |ui| {
+                            report_section(ui, "Added", report.added());
+                            report_section(ui, "Improved", report.improved());
+                            report_section(ui, "Ignored (slower or already known)", report.ignored());
+                            report_section(ui, "Rejected", report.rejected());
+                        });
+                    }
+                });
+
+            if !open {
+                self.import_result = None;
+            }
+        }
+
+        if let Some(draft) = &mut self.category_settings {
+            let mut open = true;
+            let mut apply = false;
+            let mut cancel = false;
+
+            egui::Window::new("Categories")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.heading("Obby Modes");
+                    let mut remove = None;
+                    egui::Grid::new("obby_modes")
+                        .striped(true)
+                        .num_columns(3)
+                        .show(ui, |ui| {
+                            ui.strong("Name");
+                            ui.strong("Studio tag");
+                            ui.strong("");
+                            ui.end_row();
+
+                            for (i, (existing, mode)) in draft.modes.iter_mut().enumerate() {
+                                if *existing {
+                                    ui.label(&mode.name);
+                                } else {
+                                    ui.text_edit_singleline(&mut mode.name);
+                                }
+                                ui.text_edit_singleline(&mut mode.tag);
+                                if ui.button("Remove").clicked() {
+                                    remove = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    if let Some(i) = remove {
+                        draft.modes.remove(i);
+                    }
+                    if ui.button("Add Mode").clicked() {
+                        draft.modes.push((false, ObbyMode::new("", "")));
+                    }
+                    ui.label("A mode can only be removed once it has no runs.");
+
+                    ui.separator();
+                    ui.heading("Main Obby Categories");
+                    let mut remove = None;
+                    egui::Grid::new("main_obby_categories")
+                        .striped(true)
+                        .num_columns(4)
+                        .show(ui, |ui| {
+                            ui.strong("Name");
+                            ui.strong("Studio tag");
+                            ui.strong("Slots");
+                            ui.strong("");
+                            ui.end_row();
+
+                            for (i, (existing, category)) in draft.main_obby.iter_mut().enumerate() {
+                                if *existing {
+                                    ui.label(&category.name);
+                                } else {
+                                    ui.text_edit_singleline(&mut category.name);
+                                }
+                                ui.text_edit_singleline(&mut category.tag);
+                                ui.add(egui::DragValue::new(&mut category.cap).clamp_range(1..=100));
+                                if ui.button("Remove").clicked() {
+                                    remove = Some(i);
+                                }
+                                ui.end_row();
                             }
+                        });
+                    if let Some(i) = remove {
+                        draft.main_obby.remove(i);
+                    }
+                    if ui.button("Add Category").clicked() {
+                        draft.main_obby.push((false, MainObbyCategory::new("", "", 10)));
+                    }
+                    ui.label(
+                        "Entries of a removed category or below its slots are archived and come back \
+                         when the category is added back or its slots are raised.",
+                    );
+
+                    if !draft.error.is_empty() {
+                        ui.colored_label(ui.visuals().error_fg_color, draft.error.as_str());
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Apply").clicked() {
+                            apply = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if apply {
+                let modes = draft.modes.iter().map(|(_, m)| m.clone()).collect();
+                let categories = draft.main_obby.iter().map(|(_, c)| c.clone()).collect();
+                match self.store.set_categories(modes, categories) {
+                    Ok(()) => self.category_settings = None,
+                    Err(e) => draft.error = e,
+                }
+            } else if cancel || !open {
+                self.category_settings = None;
+            }
+        }
 
-                            if let mlua::Value::String(name) = &key {
-                                let name_str = name.to_str().unwrap_or_default();
-                                if name_str == "MainObby" {
-                                    if let mlua::Value::Table(main_ob) = value {
-                                        for cat in ["Bounce", "Bounceless", "NoPlat"] {
-                                            if let Ok(sub) = main_ob.get::<_, mlua::Table>(cat) {
-                                                for r in sub.sequence_values::<mlua::Table>() {
-                                                    if let Ok(entry) = r {
-                                                        let player = entry
-                                                            .get::<_, String>(1)
-                                                            .unwrap_or_default();
-                                                        let time = entry
-                                                            .get::<_, f32>(2)
-                                                            .unwrap_or(9999.0);
-                                                        self.add_main_ob_record(player, time, cat);
-                                                    }
+        if self.show_matrix {
+            let mut open = true;
+            let mut fill: Option<(String, String)> = None;
+            let mut history: Option<String> = None;
+
+            let records = self.store.records();
+            let holders: HashMap<(&str, &str), usize> = self
+                .store
+                .current_record_indices()
+                .into_iter()
+                .map(|i| ((records[i].obby.as_str(), records[i].mode.as_str()), i))
+                .collect();
+            let modes = self.store.modes();
+            let obbies = self.store.obby_names();
+            let cells = obbies.len() * modes.len();
+            let gaps = cells.saturating_sub(holders.len());
+
+            egui::Window::new("Obby × Mode")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} of {} cells have no record.", gaps, cells));
+                        ui.checkbox(&mut self.matrix_gaps_only, "Only obbies with gaps");
+                    });
+                    ui.label("Click a filled cell for its history, or an empty one to fill in the add form.");
+                    ui.separator();
+
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        egui::Grid::new("obby_mode_matrix")
+                            .striped(true)
+                            .num_columns(modes.len() + 1)
+                            .show(ui, |ui| {
+                                ui.strong("Obby");
+                                for mode in modes {
+                                    ui.strong(&mode.name);
+                                }
+                                ui.end_row();
+
+                                for obby in obbies {
+                                    let cells: Vec<Option<&Record>> = modes
+                                        .iter()
+                                        .map(|m| holders.get(&(obby.as_str(), m.name.as_str())).map(|&i| &records[i]))
+                                        .collect();
+                                    if self.matrix_gaps_only && cells.iter().all(Option::is_some) {
+                                        continue;
+                                    }
+
+                                    ui.label(self.store.display_name(obby));
+                                    for (mode, cell) in modes.iter().zip(cells) {
+                                        match cell {
+                                            Some(record) => {
+                                                let text = format!(
+                                                    "{} - {}s{}",
+                                                    record.player,
+                                                    record.time,
+                                                    placeholder_mark(record.is_placeholder())
+                                                );
+                                                if ui.selectable_label(false, text).clicked() {
+                                                    history = Some(obby.clone());
+                                                }
+                                            }
+                                            None => {
+                                                let empty = egui::RichText::new("no record")
+                                                    .color(ui.visuals().warn_fg_color);
+                                                if ui.selectable_label(false, empty).clicked() {
+                                                    fill = Some((obby.clone(), mode.name.clone()));
                                                 }
                                             }
                                         }
                                     }
-                                    continue;
+                                    ui.end_row();
                                 }
+                            });
+                    });
+                });
+
+            if let Some((obby, mode)) = fill {
+                self.obby_input = obby;
+                self.mode_input = mode;
+            }
+            if history.is_some() {
+                self.history_view_obby = history;
+            }
+            if !open {
+                self.show_matrix = false;
+            }
+        }
+
+        if let Some(manager) = &mut self.obby_manager {
+            let mut open = true;
+            let mut result: Option<Result<(), String>> = None;
+
+            egui::Window::new("Obbies")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    let mut run_counts: HashMap<String, usize> = HashMap::new();
+                    for record in self.store.records() {
+                        *run_counts.entry(record.obby.clone()).or_default() += 1;
+                    }
+                    let obby_count = self.store.obby_names().len();
+                    let unused: Vec<String> = self
+                        .store
+                        .obby_names()
+                        .into_iter()
+                        .filter(|obby| !run_counts.contains_key(obby.as_str()))
+                        .cloned()
+                        .collect();
+
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} obbies, {} without runs.", obby_count, unused.len()));
+                        if ui
+                            .add_enabled(!unused.is_empty(), egui::Button::new("Remove Unused"))
+                            .clicked()
+                        {
+                            self.store.begin("Remove unused obbies");
+                            for obby in &unused {
+                                let _ = self.store.remove_obby(obby);
                             }
+                            self.store.commit();
+                            manager.selected = None;
+                        }
+                    });
+                    let mut identity = self.store.obby_identity();
+                    ui.horizontal(|ui| {
+                        ui.label("Same obby when names differ only in:");
+                        ui.checkbox(&mut identity.ignore_case, "case");
+                        ui.checkbox(&mut identity.ignore_whitespace, "spaces");
+                        ui.checkbox(&mut identity.ignore_punctuation, "punctuation");
+                    });
+                    self.store.set_obby_identity(identity);
 
-                            let obby_name = match &key {
-                                mlua::Value::String(s) => {
-                                    s.to_str().unwrap_or_default().to_string()
+                    let collisions = self.store.obby_collisions();
+                    if !collisions.is_empty() {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "These look like the same obby; pick one and merge it into the other:",
+                        );
+                        for group in collisions {
+                            let names: Vec<&str> = group.iter().map(|name| name.as_str()).collect();
+                            ui.label(names.join(" / "));
+                        }
+                    }
+                    ui.separator();
+
+                    egui::ScrollArea::vertical()
+                        .id_source("obby_list")
+                        .max_height(250.0)
+                        .show(ui, |ui| {
+                            for obby in self.store.obby_names() {
+                                let runs = run_counts.get(obby.as_str()).copied().unwrap_or_default();
+                                let text = format!("{} - {} runs", obby_label(&self.store, obby), runs);
+                                let selected = manager.selected.as_deref() == Some(obby.as_str());
+                                if ui.selectable_label(selected, text).clicked() {
+                                    *manager = ObbyManager {
+                                        selected: Some(obby.clone()),
+                                        new_name: obby.clone(),
+                                        display_name: self.store.display_name(obby).to_string(),
+                                        ..ObbyManager::default()
+                                    };
                                 }
-                                _ => continue,
-                            };
+                            }
+                        });
 
-                            let mode_table = match value {
-                                mlua::Value::Table(t) => t,
-                                _ => continue,
-                            };
+                    let Some(obby) = manager.selected.clone() else {
+                        ui.label("Pick an obby to rename, merge or remove it.");
+                        return;
+                    };
+                    ui.separator();
+                    ui.heading(&obby);
 
-                            for mode_pair in mode_table.pairs::<String, mlua::Table>() {
-                                let (mode, data) = mode_pair.unwrap();
-                                let bounce = mode == "Bounce";
-                                let player = data.get::<usize, String>(1).unwrap_or_default();
-                                let time = data.get::<usize, f32>(2).unwrap_or(9999.0);
-                                self.add_record_entry(&obby_name, bounce, &player, time);
-                            }
+                    egui::Grid::new("obby_actions").num_columns(3).show(ui, |ui| {
+                        ui.label("Studio name:");
+                        ui.text_edit_singleline(&mut manager.new_name);
+                        if ui.button("Rename").clicked() {
+                            result = Some(self.store.rename_obby(&obby, &manager.new_name).map(|()| {
+                                manager.selected = Some(manager.new_name.trim().to_string());
+                            }));
+                        }
+                        ui.end_row();
+
+                        ui.label("Display name:");
+                        ui.add(egui::TextEdit::singleline(&mut manager.display_name).hint_text(obby.as_str()));
+                        if ui.button("Set").clicked() {
+                            self.store.set_display_name(&obby, &manager.display_name);
+                            result = Some(Ok(()));
                         }
+                        ui.end_row();
+
+                        ui.label("Merge into:");
+                        egui::ComboBox::from_id_source("merge_into")
+                            .selected_text(&manager.merge_into)
+                            .show_ui(ui, |ui| {
+                                for other in self.store.obby_names() {
+                                    if *other != obby {
+                                        ui.selectable_value(
+                                            &mut manager.merge_into,
+                                            other.clone(),
+                                            obby_label(&self.store, other),
+                                        );
+                                    }
+                                }
+                            });
+                        if ui.button("Merge").clicked() {
+                            let into = manager.merge_into.clone();
+                            result = Some(self.store.merge_obby(&obby, &into).map(|()| {
+                                manager.selected = Some(into);
+                            }));
+                        }
+                        ui.end_row();
+                    });
+
+                    let runs = self.store.run_count(&obby);
+                    let remove = ui
+                        .add_enabled(runs == 0, egui::Button::new("Remove"))
+                        .on_disabled_hover_text("Only obbies without runs can be removed.");
+                    if remove.clicked() {
+                        result = Some(self.store.remove_obby(&obby).map(|()| manager.selected = None));
                     }
-                }
+
+                    if !manager.error.is_empty() {
+                        ui.colored_label(ui.visuals().error_fg_color, manager.error.as_str());
+                    }
+                });
+
+            match result {
+                Some(Ok(())) => manager.error.clear(),
+                Some(Err(e)) => manager.error = e,
+                None => {}
+            }
+            if !open {
+                self.obby_manager = None;
             }
         }
-    }
 
-    fn copy_to_clipboard(&self) {
-        let mut map: HashMap<String, HashMap<String, (String, f32)>> = HashMap::new();
+        if self.show_stats {
+            let mut open = true;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let stats = self.store.player_stats(now);
+            let modes = self.store.modes();
 
-        for r in &self.records {
-            let bounce_type = if r.bounce { "Bounce" } else { "Bounceless" };
-            map.entry(r.obby.clone())
-                .or_default()
-                .insert(bounce_type.to_string(), (r.player.clone(), r.time));
-        }
+            egui::Window::new("Player Stats")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} players, ranked by most records.", stats.len()));
+                        if ui.button("Copy Ranking").clicked() {
+                            let ranking: Vec<String> = stats
+                                .iter()
+                                .enumerate()
+                                .map(|(i, s)| format!("{}. {}", i + 1, s.describe()))
+                                .collect();
+                            if let Ok(mut clipboard) = Clipboard::new() {
+                                clipboard.set_text(ranking.join("\n")).ok();
+                            }
+                        }
+                    });
+                    ui.label("Time held counts from when a run was added or imported.");
+                    ui.separator();
 
-        let mut output = String::from("{\n");
-        output.push_str(&format!(
-            "  [\"CTT2Mode\"] = {},\n",
-            if self.ctt2_mode { "true" } else { "false" }
-        ));
-
-        for (obby, types) in &map {
-            output.push_str(&format!("  [\"{}\"] = {{\n", obby));
-            if let Some((player, time)) = types.get("Bounce") {
-                output.push_str(&format!(
-                    "    [\"Bounce\"] = {{ \"{}\", {:.3} }},\n",
-                    player, time
-                ));
-            }
-            if let Some((player, time)) = types.get("Bounceless") {
-                output.push_str(&format!(
-                    "    [\"Bounceless\"] = {{ \"{}\", {:.3} }},\n",
-                    player, time
-                ));
+                    egui::ScrollArea::both().show(ui, |ui| {
+                        egui::Grid::new("player_stats")
+                            .striped(true)
+                            .num_columns(modes.len() + 5)
+                            .show(ui, |ui| {
+                                ui.strong("#");
+                                ui.strong("Player");
+                                ui.strong("Records");
+                                for mode in modes {
+                                    ui.strong(&mode.name);
+                                }
+                                ui.strong("Main Obby");
+                                ui.strong("Held");
+                                ui.end_row();
+
+                                for (i, s) in stats.iter().enumerate() {
+                                    ui.label((i + 1).to_string());
+                                    ui.label(&s.player);
+                                    ui.label(s.records().to_string());
+                                    for mode in modes {
+                                        let count = s.records_by_mode.get(&mode.name).copied().unwrap_or_default();
+                                        ui.label(count.to_string());
+                                    }
+                                    let places: Vec<String> = s
+                                        .main_obby
+                                        .iter()
+                                        .map(|(category, place)| format!("{} #{}", category, place + 1))
+                                        .collect();
+                                    ui.label(places.join(", "));
+                                    ui.label(s.held_text());
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                });
+
+            if !open {
+                self.show_stats = false;
             }
-            output.push_str("  },\n");
         }
 
-        if self.ctt2_mode {
-            output.push_str("  [\"MainObby\"] = {\n");
+        if let Some(manager) = &mut self.player_manager {
+            let mut open = true;
+            let mut result: Option<Result<(), String>> = None;
 
-            let write_cat = |name: &str, list: &Vec<(String, f32)>, out: &mut String| {
-                if !list.is_empty() {
-                    out.push_str(&format!("    [\"{}\"] = {{\n", name));
-                    for (p, t) in list {
-                        out.push_str(&format!("      {{ \"{}\", {:.3} }},\n", p, t));
+            egui::Window::new("Players")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    let mut entry_counts: HashMap<&str, usize> = HashMap::new();
+                    for record in self.store.records() {
+                        *entry_counts.entry(record.player.as_str()).or_default() += 1;
+                    }
+                    for category in self.store.main_obby_categories() {
+                        for (player, _) in self.store.main_obby(&category.name).unwrap_or_default() {
+                            *entry_counts.entry(player.as_str()).or_default() += 1;
+                        }
                     }
-                    out.push_str("    },\n");
-                }
-            };
 
-            write_cat("Bounce", &self.main_ob_bounce, &mut output);
-            write_cat("Bounceless", &self.main_ob_bounceless, &mut output);
-            write_cat("NoPlat", &self.main_ob_noplat, &mut output);
+                    egui::ScrollArea::vertical()
+                        .id_source("player_list")
+                        .max_height(250.0)
+                        .show(ui, |ui| {
+                            for name in self.store.player_names() {
+                                let entries = entry_counts.get(name.as_str()).copied().unwrap_or_default();
+                                let mut text = format!("{} - {} entries", name, entries);
+                                if let Some(player) = self.store.player(name) {
+                                    if !player.aliases.is_empty() {
+                                        text += &format!(" (aka {})", player.aliases.join(", "));
+                                    }
+                                    if let Some(id) = player.roblox_id {
+                                        text += &format!(" #{}", id);
+                                    }
+                                }
+                                let selected = manager.selected.as_deref() == Some(name.as_str());
+                                if ui.selectable_label(selected, text).clicked() {
+                                    let player = self.store.player(name);
+                                    *manager = PlayerManager {
+                                        selected: Some(name.clone()),
+                                        new_name: name.clone(),
+                                        aliases: player.map(|p| p.aliases.join(", ")).unwrap_or_default(),
+                                        roblox_id: player
+                                            .and_then(|p| p.roblox_id)
+                                            .map(|id| id.to_string())
+                                            .unwrap_or_default(),
+                                        ..PlayerManager::default()
+                                    };
+                                }
+                            }
+                        });
 
-            output.push_str("  },\n");
-        }
+                    let Some(name) = manager.selected.clone() else {
+                        ui.label("Pick a player to rename, merge or give aliases.");
+                        return;
+                    };
+                    ui.separator();
+                    ui.heading(&name);
+
+                    egui::Grid::new("player_actions").num_columns(3).show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut manager.new_name);
+                        if ui.button("Rename").clicked() {
+                            result = Some(self.store.rename_player(&name, &manager.new_name).map(|()| {
+                                manager.selected = Some(manager.new_name.trim().to_string());
+                            }));
+                        }
+                        ui.end_row();
+
+                        ui.label("Aliases:");
+                        ui.add(egui::TextEdit::singleline(&mut manager.aliases).hint_text("old_name, other_name"));
+                        ui.end_row();
+
+                        ui.label("Roblox user ID:");
+                        ui.add(egui::TextEdit::singleline(&mut manager.roblox_id).hint_text("optional"));
+                        if ui.button("Save").clicked() {
+                            let aliases = manager.aliases.split(',').map(str::to_string).collect();
+                            let id = manager.roblox_id.trim();
+                            result = Some(if id.is_empty() {
+                                self.store.set_player_info(&name, aliases, None)
+                            } else {
+                                id.parse::<u64>()
+                                    .map_err(|_| format!("'{}' is not a Roblox user ID", id))
+                                    .and_then(|id| self.store.set_player_info(&name, aliases, Some(id)))
+                            });
+                        }
+                        ui.end_row();
+
+                        ui.label("Merge into:");
+                        egui::ComboBox::from_id_source("merge_player_into")
+                            .selected_text(&manager.merge_into)
+                            .show_ui(ui, |ui| {
+                                for other in self.store.player_names() {
+                                    if *other != name {
+                                        ui.selectable_value(&mut manager.merge_into, other.clone(), other);
+                                    }
+                                }
+                            });
+                        if ui.button("Merge").clicked() {
+                            let into = manager.merge_into.clone();
+                            result = Some(self.store.merge_player(&name, &into).map(|()| {
+                                manager.selected = Some(into);
+                            }));
+                        }
+                        ui.end_row();
+                    });
 
-        output.push_str("}");
+                    if !manager.error.is_empty() {
+                        ui.colored_label(ui.visuals().error_fg_color, manager.error.as_str());
+                    }
+                });
 
-        if self.real_time_enabled {
-            if let Ok(mut data) = self.http_data.lock() {
-                *data = self.generate_json_export();
+            match result {
+                Some(Ok(())) => manager.error.clear(),
+                Some(Err(e)) => manager.error = e,
+                None => {}
+            }
+            if !open {
+                self.player_manager = None;
             }
-        }        
+        }
 
-        if let Ok(mut clipboard) = Clipboard::new() {
-            clipboard.set_text(output).ok();
+        if self
+            .entry_editor
+            .as_ref()
+            .is_some_and(|editor| editor.revision != self.store.revision())
+        {
+            self.entry_editor = None;
         }
-    }
+        if let Some(editor) = &mut self.entry_editor {
+            let mut open = true;
+            let mut save = false;
+            let mut cancel = false;
 
-    fn delete_record(&mut self, index: usize) {
-        self.records.remove(index);
-    }
-}
+            egui::Window::new("Edit Entry")
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    egui::Grid::new("entry_editor").num_columns(2).show(ui, |ui| {
+                        ui.label("Player Name:");
+                        ui.text_edit_singleline(&mut editor.player);
+                        ui.end_row();
+
+                        ui.label("Time:");
+                        ui.add(egui::TextEdit::singleline(&mut editor.time).hint_text("1:23.456"));
+                        ui.end_row();
+
+                        match &editor.target {
+                            EditTarget::Run(_) => {
+                                ui.label("Obby Name:");
+                                ui.text_edit_singleline(&mut editor.obby);
+                                ui.end_row();
+
+                                ui.label("Mode:");
+                                egui::ComboBox::from_id_source("edit_mode")
+                                    .selected_text(&editor.mode)
+                                    .show_ui(ui, |ui| {
+                                        for mode in self.store.modes() {
+                                            ui.selectable_value(&mut editor.mode, mode.name.clone(), &mode.name);
+                                        }
+                                    });
+                                ui.end_row();
+                            }
+                            EditTarget::MainObby { category, .. } => {
+                                ui.label("Category:");
+                                ui.label(category);
+                                ui.end_row();
+                            }
+                        }
+
+                        ui.label("Reason:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut editor.reason)
+                                .hint_text("needed to make a time slower"),
+                        );
+                        ui.end_row();
+                    });
+                    let player_ok = player_hint(ui, &self.store, &mut editor.player, &mut self.approved_names);
+                    time_hint(ui, &editor.time);
+
+                    if !editor.error.is_empty() {
+                        ui.colored_label(ui.visuals().error_fg_color, editor.error.as_str());
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(player_ok, egui::Button::new("Save")).clicked() {
+                            save = true;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+
+            if save {
+                let result = editor.time.parse::<Time>().map_err(|e| e.to_string()).and_then(|time| {
+                    match &editor.target {
+                        EditTarget::Run(index) => {
+                            let Some(run) = self.store.records().get(*index) else {
+                                return Err("that run no longer exists".to_string());
+                            };
+                            let edited = Record {
+                                player: editor.player.clone(),
+                                time,
+                                obby: editor.obby.clone(),
+                                mode: editor.mode.clone(),
+                                ..run.clone()
+                            };
+                            self.store.edit_record(*index, edited, &editor.reason)
+                        }
+                        EditTarget::MainObby { category, place } => {
+                            self.store
+                                .edit_main_obby_entry(category, *place, &editor.player, time, &editor.reason)
+                        }
+                    }
+                });
+                match result {
+                    Ok(()) => self.entry_editor = None,
+                    Err(e) => editor.error = e,
+                }
+            } else if cancel || !open {
+                self.entry_editor = None;
+            }
+        }
 
-impl eframe::App for AppState {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::ScrollArea::vertical().show(ui, |ui| {
                 ui.heading("World Record Editor");
@@ -341,22 +1287,29 @@ impl eframe::App for AppState {
                     ui.separator();
                 
                     ui.heading("How to Use (Rust App)");
-                    ui.label("1. Enter player name, obby name, and time.");
-                    ui.label("2. Toggle Bounce if it's a bounce record.");
+                    ui.label("1. Enter player name, obby name, and time as s.mmm, m:ss.mmm or h:mm:ss.mmm.");
+                    ui.label("2. Pick the mode, e.g. Bounce or Bounceless. 'Categories...' adds more modes.");
                     ui.label("3. Click 'Add Record' to add it to the list.");
                     ui.label("4. Click 'Copy to Clipboard' to export in Lua format.");
                     ui.label("5. Use 'Import from Clipboard' to paste records from Roblox. (see roblox studio guide)");
-                    ui.label("6. Use the Delete button to remove entries.");
+                    ui.label("   A preview shows what every entry would change; untick rows you don't want and click 'Apply Selected'.");
+                    ui.label("6. Use the Delete button to remove entries. The next fastest run becomes the record.");
                     ui.label("7. Use the 'CTT2 Mode' toggle if you're targeting the CTT2 folder structure in Roblox.");
+                    ui.label("8. Use 'Save' and 'Open' next to Session File to keep your records between runs.");
+                    ui.label("9. The session is also autosaved every 30 seconds and offered for recovery after a crash.");
+                    ui.label("10. Undo and Redo (Ctrl+Z / Ctrl+Shift+Z) revert adds, deletes and whole imports.");
+                    ui.label("11. Slower runs are kept; click 'History' on a record to see every run of that obby.");
+                    ui.label("12. Tick 'Top-N board' in an obby's History to also export its fastest players as a ranked list.");
                 
                     ui.separator();
                 
                     ui.heading("How to Use (Main Obby Records)");
                     ui.label("Only visible when CTT2 Mode is enabled.");
-                    ui.label("1. Choose player, time and category (Bounce, Bounceless or NoPlat).");
-                    ui.label("2. Max 12 for Bounce, 11 for Bounceless, 10 for NoPlat.");
+                    ui.label("1. Choose player, time and category (Bounce, Bounceless, NoPlat or your own).");
+                    ui.label("2. 'Categories...' sets each category's Studio tag and number of slots (12/11/10 by default).");
                     ui.label("3. Records are sorted automatically by time.");
-                    ui.label("4. Export will include them in the MainObby section.");
+                    ui.label("4. Each player keeps only their best time unless 'Allow several entries per player' is on.");
+                    ui.label("5. Export will include them in the MainObby section.");
                 
                     ui.separator();
                 
@@ -367,31 +1320,67 @@ impl eframe::App for AppState {
                     ui.label("4. To export: print(require(game.ReplicatedStorage.Modules.RecordModule).get_records(true_or_false_for_ctt2mode))");
                     ui.label("5. If CTT2Mode is true, it will use workspace.MISC.LBS.[OBBYNAME:UPPER()].B or NB");
                     ui.label("6. If not, it uses the traditional '[ObbyName][Bounce|Bounceless]Leaderboard' format.");
-                    ui.label("7. For MainObby, it updates MISC.LBS.MO.[B/NB/NT].LB.Leaderboard.ScrollingFrame entries 1–12.");
+                    ui.label("7. For MainObby, it updates MISC.LBS.MO.[tag].LB.Leaderboard.ScrollingFrame entries 1, 2, ... using the exported MainObbyTags.");
                 
                     return;
                 }                
 
+                ui.horizontal(|ui| {
+                    ui.label("Session File:");
+                    ui.text_edit_singleline(&mut self.session_path_input);
+                    if ui.button("Save").clicked() {
+                        self.save_session();
+                    }
+                    if ui.button("Open").clicked() {
+                        self.open_session();
+                    }
+                });
+                if !self.session_status.is_empty() {
+                    ui.label(&self.session_status);
+                }
+
+                ui.horizontal(|ui| {
+                    let undo_label = self.store.undo_label().map(|l| format!("Undo {}", l));
+                    let redo_label = self.store.redo_label().map(|l| format!("Redo {}", l));
+
+                    let undo_button = ui.add_enabled(undo_label.is_some(), egui::Button::new("Undo"));
+                    if undo_button.on_hover_text(undo_label.unwrap_or_default()).clicked() {
+                        self.store.undo();
+                    }
+
+                    let redo_button = ui.add_enabled(redo_label.is_some(), egui::Button::new("Redo"));
+                    if redo_button.on_hover_text(redo_label.unwrap_or_default()).clicked() {
+                        self.store.redo();
+                    }
+                });
+
+                ui.separator();
+
                 ui.horizontal(|ui| {
                     ui.label("Player Name:");
                     ui.text_edit_singleline(&mut self.player_input);
+                    if ui.button("Players...").clicked() {
+                        self.player_manager = Some(PlayerManager::default());
+                    }
                 });
+                let player_ok = player_hint(ui, &self.store, &mut self.player_input, &mut self.approved_names);
 
                 ui.horizontal(|ui| {
-                    ui.label("Time (s):");
-                    ui.text_edit_singleline(&mut self.time_input);
+                    ui.label("Time:");
+                    ui.add(egui::TextEdit::singleline(&mut self.time_input).hint_text("1:23.456"));
                 });
+                time_hint(ui, &self.time_input);
 
                 ui.horizontal(|ui| {
                     ui.label("Obby Name:");
                 
-                    if !self.obby_names.is_empty() {
+                    if !self.store.obby_names().is_empty() {
                         egui::ComboBox::from_id_source("obby_dropdown")
                             .width(160.0)
                             .selected_text(&self.obby_input)
                             .show_ui(ui, |ui| {
-                                for obby in &self.obby_names {
-                                    ui.selectable_value(&mut self.obby_input, obby.clone(), obby);
+                                for obby in self.store.obby_names() {
+                                    ui.selectable_value(&mut self.obby_input, obby.clone(), obby_label(&self.store, obby));
                                 }
                             });
                 
@@ -399,38 +1388,145 @@ impl eframe::App for AppState {
                     }
                 
                     ui.text_edit_singleline(&mut self.obby_input);
-                });                                               
+                    if ui.button("Obbies...").clicked() {
+                        self.obby_manager = Some(ObbyManager::default());
+                    }
+                });
+                if let Some(existing) = self.store.find_obby(&self.obby_input)
+                    && existing != self.obby_input.trim()
+                {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Same obby as '{}'; the run will be added there.", existing),
+                    );
+                }                                               
 
-                ui.checkbox(&mut self.is_bounce, "Bounce");
+                ui.horizontal(|ui| {
+                    ui.label("Mode:");
+                    egui::ComboBox::from_id_source("mode")
+                        .selected_text(&self.mode_input)
+                        .show_ui(ui, |ui| {
+                            for mode in self.store.modes() {
+                                ui.selectable_value(&mut self.mode_input, mode.name.clone(), &mode.name);
+                            }
+                        });
+                    if ui.button("Categories...").clicked() {
+                        self.open_category_settings();
+                    }
+                });
 
-                if ui.button("Add Record").clicked() {
+                if ui.add_enabled(player_ok, egui::Button::new("Add Record")).clicked() {
                     self.add_record();
                 }
+                if let Some(message) = &self.add_record_message {
+                    ui.colored_label(ui.visuals().warn_fg_color, message.as_str());
+                }
 
                 ui.separator();
                 ui.heading("Records");
 
+                let placeholders = self.store.placeholder_count();
+                if placeholders > 0 {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "{} entries look like placeholders from an old import (empty name or a time of exactly 9999s). \
+                             Check the ones marked {} here, in History and in Main Obby, and delete them.",
+                            placeholders, PLACEHOLDER_MARK
+                        ),
+                    );
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Filter:");
+                    ui.add(egui::TextEdit::singleline(&mut self.record_filter).hint_text("obby or player"));
+                    egui::ComboBox::from_id_source("record_mode_filter")
+                        .selected_text(self.record_mode_filter.as_deref().unwrap_or("All modes"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.record_mode_filter, None, "All modes");
+                            for mode in self.store.modes() {
+                                ui.selectable_value(&mut self.record_mode_filter, Some(mode.name.clone()), &mode.name);
+                            }
+                        });
+                });
+
+                ui.horizontal(|ui| {
+                    if ui.button("Obby × Mode Matrix").clicked() {
+                        self.show_matrix = true;
+                    }
+                    if ui.button("Player Stats").clicked() {
+                        self.show_stats = true;
+                    }
+                });
+
+                let rows = self.table_rows();
+                let row_height = ui.spacing().interact_size.y;
+
+                ui.horizontal(|ui| {
+                    for (column, title, width) in RecordColumn::ALL {
+                        let sorted = self.record_sort == column;
+                        let arrow = match (sorted, self.record_sort_ascending) {
+                            (false, _) => "",
+                            (true, true) => " ▲",
+                            (true, false) => " ▼",
+                        };
+                        let header = egui::SelectableLabel::new(sorted, format!("{}{}", title, arrow));
+                        if ui.add_sized([width, row_height], header).clicked() {
+                            self.record_sort_ascending = !sorted || !self.record_sort_ascending;
+                            self.record_sort = column;
+                        }
+                    }
+                    ui.label(format!("{} shown", rows.len()));
+                });
+
                 let mut to_delete: Option<usize> = None;
-                for (i, record) in self.records.iter().enumerate() {
-                    ui.horizontal(|ui| {
-                        ui.label(format!(
-                            "{} - {} - {} - {:.3}s",
-                            record.obby,
-                            if record.bounce {
-                                "Bounce"
-                            } else {
-                                "Bounceless"
-                            },
-                            record.player,
-                            record.time
-                        ));
-                        if ui.button("Delete").clicked() {
-                            to_delete = Some(i);
+                let mut to_edit: Option<usize> = None;
+                egui::ScrollArea::vertical()
+                    .id_source("records_table")
+                    .max_height(400.0)
+                    .auto_shrink([false, true])
+                    .show_rows(ui, row_height, rows.len(), |ui, range| {
+                        for &i in &rows[range] {
+                            let record = &self.store.records()[i];
+                            ui.horizontal(|ui| {
+                                let cells = [
+                                    obby_label(&self.store, &record.obby),
+                                    record.mode.clone(),
+                                    record.player.clone(),
+                                    format!("{}s{}", record.time, placeholder_mark(record.is_placeholder())),
+                                ];
+                                for ((_, _, width), text) in RecordColumn::ALL.into_iter().zip(cells) {
+                                    ui.add_sized([width, row_height], egui::Label::new(text).truncate(true));
+                                }
+                                if ui.button("Edit").clicked() {
+                                    to_edit = Some(i);
+                                }
+                                if ui.button("Delete").clicked() {
+                                    to_delete = Some(i);
+                                }
+                                if ui.button("History").clicked() {
+                                    self.history_view_obby = Some(record.obby.clone());
+                                }
+                            });
                         }
                     });
+                if let Some(i) = to_edit {
+                    self.edit_run(i);
                 }
                 if let Some(i) = to_delete {
-                    self.delete_record(i);
+                    self.store.delete_record(i);
+                }
+
+                if !self.store.corrections().is_empty() {
+                    egui::CollapsingHeader::new(format!("Corrections ({})", self.store.corrections().len()))
+                        .show(ui, |ui| {
+                            for c in self.store.corrections() {
+                                ui.label(format!(
+                                    "{}: {} - {}s -> {} - {}s ({})",
+                                    c.target, c.before.0, c.before.1, c.after.0, c.after.1, c.reason
+                                ));
+                            }
+                        });
                 }
 
                 ui.separator();
@@ -444,9 +1540,12 @@ impl eframe::App for AppState {
                 }
 
                 ui.separator();
-                ui.checkbox(&mut self.ctt2_mode, "CTT2 Mode");
+                let mut ctt2_mode = self.store.ctt2_mode();
+                if ui.checkbox(&mut ctt2_mode, "CTT2 Mode").changed() {
+                    self.store.set_ctt2_mode(ctt2_mode);
+                }
 
-                if self.ctt2_mode {
+                if self.store.ctt2_mode() {
                     ui.separator();
                     ui.heading("Main Obby Records");
 
@@ -454,57 +1553,102 @@ impl eframe::App for AppState {
                         ui.label("Player Name:");
                         ui.text_edit_singleline(&mut self.main_player_input);
                     });
+                    let main_player_ok =
+                        player_hint(ui, &self.store, &mut self.main_player_input, &mut self.approved_names);
 
                     ui.horizontal(|ui| {
-                        ui.label("Time (s):");
-                        ui.text_edit_singleline(&mut self.main_time_input);
+                        ui.label("Time:");
+                        ui.add(egui::TextEdit::singleline(&mut self.main_time_input).hint_text("1:23.456"));
                     });
+                    time_hint(ui, &self.main_time_input);
 
                     ui.horizontal(|ui| {
                         ui.label("Category:");
                         egui::ComboBox::from_id_source("main_category")
                             .selected_text(&self.main_category)
                             .show_ui(ui, |ui| {
-                                ui.selectable_value(
-                                    &mut self.main_category,
-                                    "Bounce".to_string(),
-                                    "Bounce",
-                                );
-                                ui.selectable_value(
-                                    &mut self.main_category,
-                                    "Bounceless".to_string(),
-                                    "Bounceless",
-                                );
-                                ui.selectable_value(
-                                    &mut self.main_category,
-                                    "NoPlat".to_string(),
-                                    "NoPlat",
-                                );
+                                for category in self.store.main_obby_categories() {
+                                    ui.selectable_value(
+                                        &mut self.main_category,
+                                        category.name.clone(),
+                                        &category.name,
+                                    );
+                                }
                             });
+                        if ui.button("Categories...").clicked() {
+                            self.open_category_settings();
+                        }
                     });
 
-                    if ui.button("Add Main Obby Record").clicked() {
-                        if let Ok(t) = self.main_time_input.parse::<f32>() {
-                            let cat = self.main_category.clone();
-                            let player = self.main_player_input.clone();
-                            self.add_main_ob_record(player, t, &cat);
-                            self.main_player_input.clear();
-                            self.main_time_input.clear();
-                        }
+                    let mut allow_multiple = self.store.main_obby_rule() == MainObbyRule::AllowMultiple;
+                    if ui.checkbox(&mut allow_multiple, "Allow several entries per player").changed() {
+                        self.store.set_main_obby_rule(if allow_multiple {
+                            MainObbyRule::AllowMultiple
+                        } else {
+                            MainObbyRule::BestPerPlayer
+                        });
                     }
 
-                    for (label, list) in [
-                        ("Bounce", &self.main_ob_bounce),
-                        ("Bounceless", &self.main_ob_bounceless),
-                        ("NoPlat", &self.main_ob_noplat),
-                    ] {
+                    if ui
+                        .add_enabled(main_player_ok, egui::Button::new("Add Main Obby Record"))
+                        .clicked()
+                        && !self.main_player_input.trim().is_empty()
+                        && let Ok(t) = self.main_time_input.parse::<Time>()
+                    {
+                        let cat = self.main_category.clone();
+                        let player = self.main_player_input.trim().to_string();
+                        self.store.begin("Add main obby record");
+                        self.store.add_main_ob_record(player, t, &cat);
+                        self.store.commit();
+                        self.main_player_input.clear();
+                        self.main_time_input.clear();
+                    }
+
+                    let mut to_edit: Option<(String, usize)> = None;
+                    let mut to_delete: Option<(String, usize)> = None;
+                    for category in self.store.main_obby_categories() {
+                        let list = self.store.main_obby(&category.name).unwrap_or_default();
+                        let archive = self.store.main_obby_archive(&category.name).unwrap_or_default();
+                        let mut entry_row = |ui: &mut egui::Ui, i: usize, (p, t): &(String, Time)| {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}. {} - {}s{}",
+                                    i + 1,
+                                    p,
+                                    t,
+                                    placeholder_mark(is_placeholder(p, *t))
+                                ));
+                                if ui.button("Edit").clicked() {
+                                    to_edit = Some((category.name.clone(), i));
+                                }
+                                if ui.button("Delete").clicked() {
+                                    to_delete = Some((category.name.clone(), i));
+                                }
+                            });
+                        };
                         ui.group(|ui| {
-                            ui.heading(label);
-                            for (i, (p, t)) in list.iter().enumerate() {
-                                ui.label(format!("{}. {} - {:.3}s", i + 1, p, t));
+                            ui.heading(&category.name);
+                            for (i, entry) in list.iter().enumerate() {
+                                entry_row(ui, i, entry);
+                            }
+                            if !archive.is_empty() {
+                                egui::CollapsingHeader::new(format!("Archive ({})", archive.len()))
+                                    .id_source(("main_obby_archive", &category.name))
+                                    .show(ui, |ui| {
+                                        ui.label("Past the cap. Deleting an entry above moves the best one up.");
+                                        for (i, entry) in archive.iter().enumerate() {
+                                            entry_row(ui, list.len() + i, entry);
+                                        }
+                                    });
                             }
                         });
                     }
+                    if let Some((category, place)) = to_edit {
+                        self.edit_main_obby_entry(&category, place);
+                    }
+                    if let Some((category, place)) = to_delete {
+                        self.store.delete_main_obby_entry(&category, place);
+                    }
                 }
 
                 if ui.button("How to Use").clicked() {
@@ -517,36 +1661,144 @@ impl eframe::App for AppState {
                         self.http_thread = Some(handle);
                     }
                 
-                    if self.real_time_enabled {
-                        if let Ok(mut data) = self.http_data.lock() {
-                            *data = self.generate_json_export();
-                        }
-                    }
+                    self.refresh_http_data();
                 }                        
             });
         });
+
+        if self.store.revision() != self.published_revision {
+            self.refresh_http_data();
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if !self.recovery_prompt {
+            self.autosave();
+        }
+        session::release_lock();
+    }
+}
+
+
+fn report_section<'a>(ui: &mut egui::Ui, title: &str, items: impl Iterator<Item = &'a ImportItem>) {
+    let items: Vec<&ImportItem> = items.collect();
+    egui::CollapsingHeader::new(format!("{} ({})", title, items.len()))
+        .default_open(!items.is_empty())
+        .show(ui, |ui| {
+            for item in items {
+                ui.label(item.describe());
+            }
+        });
+}
+
+const PLACEHOLDER_MARK: &str = "⚠";
+
+fn placeholder_mark(placeholder: bool) -> String {
+    if placeholder {
+        format!(" {}", PLACEHOLDER_MARK)
+    } else {
+        String::new()
+    }
+}
+
+/// Explains under a time field why its text is not a valid time.
+fn time_hint(ui: &mut egui::Ui, input: &str) {
+    if !input.trim().is_empty()
+        && let Err(e) = input.parse::<Time>()
+    {
+        ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+    }
+}
+
+/// Suggests known players under a player name field and warns about new names
+/// Roblox would not allow. Returns whether the name can be used: it is valid,
+/// already known, or was kept with "Use anyway".
+fn player_hint(ui: &mut egui::Ui, store: &RecordStore, input: &mut String, approved: &mut HashSet<String>) -> bool {
+    let name = input.trim().to_string();
+    if name.is_empty() {
+        return true;
+    }
+
+    let suggestions = store.player_suggestions(&name, 5);
+    if !suggestions.is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Known:");
+            for (shown, player) in suggestions {
+                let text = if shown == player {
+                    shown.clone()
+                } else {
+                    format!("{} → {}", shown, player)
+                };
+                if ui.small_button(text).clicked() {
+                    *input = player.clone();
+                }
+            }
+        });
     }
+
+    if let Some(player) = store.player(&name) {
+        if player.name != name {
+            ui.label(format!("'{}' is an alias; the entry is saved as {}.", name, player.name));
+        }
+        return true;
+    }
+    if approved.contains(&name) || store.player_names().iter().any(|p| **p == name) {
+        return true;
+    }
+    let Some(problem) = username_problem(&name) else {
+        return true;
+    };
+
+    let mut ok = false;
+    ui.horizontal(|ui| {
+        ui.colored_label(ui.visuals().warn_fg_color, problem);
+        if ui.small_button("Use anyway").clicked() {
+            approved.insert(name.clone());
+            ok = true;
+        }
+    });
+    ok
 }
 
+/// An obby's display name, followed by its Studio folder name when they differ.
+fn obby_label(store: &RecordStore, obby: &str) -> String {
+    match store.display_name(obby) {
+        name if name == obby => obby.to_string(),
+        name => format!("{} ({})", name, obby),
+    }
+}
 
 fn spawn_http_server(shared_data: Arc<Mutex<String>>) -> thread::JoinHandle<()> {
     thread::spawn(move || {
-        let server = Server::http("127.0.0.1:14855").unwrap();
+        let server = Server::http(HTTP_ADDR).unwrap();
         for request in server.incoming_requests() {
             let data = shared_data.lock().unwrap().clone();
-            let response = Response::from_string(data)
-                .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/plain"[..]).unwrap());
-
-            let _ = request.respond(response);
+            let _ = request.respond(text_response(data));
         }
     })
 }
 
+fn text_response(data: String) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(data)
+        .with_header(tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"text/plain"[..]).unwrap())
+}
+
 fn main() -> Result<(), eframe::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
+
     let options = eframe::NativeOptions::default();
+    let unclean_shutdown = session::acquire_lock();
+    let app = AppState {
+        recovery_prompt: unclean_shutdown && session::autosave_path().exists(),
+        ..Default::default()
+    };
+
     eframe::run_native(
         "Valk's Record Adder™",
         options,
-        Box::new(|_cc| Box::<AppState>::default()),
+        Box::new(|_cc| Box::new(app)),
     )
 }
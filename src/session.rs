@@ -0,0 +1,185 @@
+use crate::RecordStore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const SESSION_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SessionRef<'a> {
+    version: u32,
+    #[serde(flatten)]
+    store: &'a RecordStore,
+}
+
+#[derive(Deserialize)]
+struct SessionFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(flatten)]
+    store: RecordStore,
+}
+
+pub fn save(path: &Path, store: &RecordStore) -> io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let json = serde_json::to_string_pretty(&SessionRef {
+        version: SESSION_VERSION,
+        store,
+    })?;
+
+    // Write next to the target and rename, so a crash mid-write never leaves a
+    // truncated session behind.
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+pub fn load(path: &Path) -> io::Result<RecordStore> {
+    let text = fs::read_to_string(path)?;
+    let session: SessionFile = serde_json::from_str(&text)?;
+
+    if session.version > SESSION_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "session was written by a newer version (format {})",
+                session.version
+            ),
+        ));
+    }
+
+    let mut store = session.store;
+    store.finish_load();
+    Ok(store)
+}
+
+fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("RecordAdder")
+}
+
+pub fn autosave_path() -> PathBuf {
+    data_dir().join("autosave.json")
+}
+
+fn lock_path() -> PathBuf {
+    data_dir().join("session.lock")
+}
+
+/// Marks the session as running. Returns true if the previous session never
+/// released its lock, i.e. it did not shut down cleanly.
+pub fn acquire_lock() -> bool {
+    let lock = lock_path();
+    let unclean = lock.exists();
+
+    if let Some(parent) = lock.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let _ = fs::write(&lock, std::process::id().to_string());
+
+    unclean
+}
+
+pub fn release_lock() {
+    let _ = fs::remove_file(lock_path());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Time;
+
+    /// A file in the system temp dir that is removed again when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            TempFile(std::env::temp_dir().join(format!("recordadder-{}-{}.json", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn saved_sessions_load_back_unchanged() {
+        let mut store = RecordStore::new();
+        store.set_ctt2_mode(true);
+        store.add_record_entry("Lava Tower", "Bounce", "amy", Time::from_millis(12_345));
+        store.add_record_entry("Lava Tower", "Bounce", "bob", Time::from_millis(13_000));
+        store.add_main_ob_record("cat".to_string(), Time::from_millis(95_500), "NoPlat");
+        store.set_board_size("Lava Tower", Some(5)).unwrap();
+        store.set_display_name("Lava Tower", "The Lava Tower");
+
+        let file = TempFile::new("round-trip");
+        save(&file.0, &store).unwrap();
+        assert!(!file.0.with_extension("tmp").exists());
+        let loaded = load(&file.0).unwrap();
+
+        assert_eq!(loaded.records(), store.records());
+        assert!(loaded.ctt2_mode());
+        assert_eq!(loaded.main_obby("NoPlat"), store.main_obby("NoPlat"));
+        assert_eq!(loaded.board_size("Lava Tower"), Some(5));
+        assert_eq!(loaded.display_name("Lava Tower"), "The Lava Tower");
+        assert_eq!(loaded.obby_names(), store.obby_names());
+    }
+
+    #[test]
+    fn sessions_from_a_newer_version_are_refused() {
+        let file = TempFile::new("newer");
+        fs::write(&file.0, r#"{ "version": 2, "ctt2_mode": false, "records": [] }"#).unwrap();
+
+        let error = load(&file.0).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("newer version"));
+    }
+
+    #[test]
+    fn first_session_format_still_loads() {
+        // As the first version with sessions wrote them: a bounce flag instead
+        // of a mode, and one list of f32 times per Main Obby category.
+        let file = TempFile::new("legacy");
+        fs::write(
+            &file.0,
+            r#"{
+                "version": 1,
+                "ctt2_mode": true,
+                "records": [
+                    { "player": "amy", "time": 83.456, "bounce": true, "obby": "Lava Tower" },
+                    { "player": "bob", "time": 20.5, "bounce": false, "obby": "Lava Tower" }
+                ],
+                "main_ob_bounce": [["amy", 95.5], ["bob", 90.25], ["amy", 95.5]],
+                "main_ob_bounceless": [],
+                "main_ob_noplat": [["cat", 120.0]],
+                "obby_names": ["Lava Tower", "Ice Cave"]
+            }"#,
+        )
+        .unwrap();
+
+        let store = load(&file.0).unwrap();
+        let modes: Vec<(&str, Time)> = store.records().iter().map(|r| (r.mode.as_str(), r.time)).collect();
+        assert_eq!(
+            modes,
+            [("Bounce", Time::from_millis(83_456)), ("Bounceless", Time::from_millis(20_500))]
+        );
+        assert_eq!(
+            store.main_obby("Bounce").unwrap(),
+            &[
+                ("bob".to_string(), Time::from_millis(90_250)),
+                ("amy".to_string(), Time::from_millis(95_500))
+            ]
+        );
+        assert_eq!(store.main_obby("NoPlat").unwrap(), &[("cat".to_string(), Time::from_millis(120_000))]);
+        assert_eq!(store.obby_names(), ["Ice Cave", "Lava Tower"]);
+    }
+}
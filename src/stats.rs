@@ -0,0 +1,192 @@
+use crate::store::RecordStore;
+use std::collections::{BTreeMap, HashMap};
+
+/// What a player holds, for the stats panel and the `stats` command.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerStats {
+    pub player: String,
+    /// Obby records held, per mode.
+    pub records_by_mode: BTreeMap<String, usize>,
+    /// Main Obby leaderboard slots as category and 0-based place.
+    pub main_obby: Vec<(String, usize)>,
+    /// Seconds spent holding obby records, summed over every obby/mode. Runs
+    /// from old sessions without a submission time count nothing.
+    pub held_secs: u64,
+}
+
+impl PlayerStats {
+    /// Obby records held over all modes.
+    pub fn records(&self) -> usize {
+        self.records_by_mode.values().sum()
+    }
+
+    /// Time held as days and hours, or minutes when under an hour.
+    pub fn held_text(&self) -> String {
+        let (days, hours, minutes) = (self.held_secs / 86_400, self.held_secs / 3_600 % 24, self.held_secs / 60 % 60);
+        match (days, hours) {
+            (0, 0) => format!("{}m", minutes),
+            (0, _) => format!("{}h {}m", hours, minutes),
+            _ => format!("{}d {}h", days, hours),
+        }
+    }
+
+    /// One line for a ranking, e.g. "amy - 3 records (Bounce 2, Bounceless 1)
+    /// - Main Obby: NoPlat #2 - held 4d 3h".
+    pub fn describe(&self) -> String {
+        let records = self.records();
+        let mut line = format!("{} - {} record{}", self.player, records, if records == 1 { "" } else { "s" });
+        if !self.records_by_mode.is_empty() {
+            let modes: Vec<String> = self.records_by_mode.iter().map(|(mode, n)| format!("{} {}", mode, n)).collect();
+            line += &format!(" ({})", modes.join(", "));
+        }
+        if !self.main_obby.is_empty() {
+            let places: Vec<String> = self
+                .main_obby
+                .iter()
+                .map(|(category, place)| format!("{} #{}", category, place + 1))
+                .collect();
+            line += &format!(" - Main Obby: {}", places.join(", "));
+        }
+        if self.held_secs > 0 {
+            line += &format!(" - held {}", self.held_text());
+        }
+        line
+    }
+}
+
+impl RecordStore {
+    /// Stats for every player with a run or Main Obby entry, most records
+    /// first. A record is held from the submission of its run until a faster
+    /// run is submitted, or until `now` (seconds since the Unix epoch). Runs
+    /// from sessions saved before submission times were kept have no start,
+    /// so holding them adds no time.
+    pub fn player_stats(&self, now: u64) -> Vec<PlayerStats> {
+        let records = self.records();
+        let mut stats: HashMap<String, PlayerStats> = HashMap::new();
+
+        for record in self.current_records() {
+            *stats_for(&mut stats, &record.player)
+                .records_by_mode
+                .entry(record.mode.clone())
+                .or_default() += 1;
+        }
+
+        for category in self.main_obby_categories() {
+            for (place, (player, _)) in self.main_obby(&category.name).unwrap_or_default().iter().enumerate() {
+                stats_for(&mut stats, player).main_obby.push((category.name.clone(), place));
+            }
+        }
+
+        // Replay every obby/mode in submission order to see who held it when.
+        let mut runs: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        for (i, record) in records.iter().enumerate() {
+            runs.entry((&record.obby, &record.mode)).or_default().push(i);
+        }
+        let mut credit = |holder: usize, until: u64| {
+            let since = records[holder].submitted;
+            if since != 0 {
+                stats_for(&mut stats, &records[holder].player).held_secs += until.saturating_sub(since);
+            }
+        };
+        for mut indices in runs.into_values() {
+            indices.sort_by_key(|&i| (records[i].submitted, i));
+            let mut holder = indices[0];
+            for &i in &indices[1..] {
+                if records[i].time < records[holder].time {
+                    credit(holder, records[i].submitted);
+                    holder = i;
+                }
+            }
+            credit(holder, now);
+        }
+
+        for record in records {
+            stats_for(&mut stats, &record.player);
+        }
+
+        let mut stats: Vec<PlayerStats> = stats.into_values().collect();
+        stats.sort_by_cached_key(|s| {
+            (
+                std::cmp::Reverse(s.records()),
+                std::cmp::Reverse(s.main_obby.len()),
+                std::cmp::Reverse(s.held_secs),
+                s.player.to_lowercase(),
+            )
+        });
+        stats
+    }
+}
+
+fn stats_for<'a>(stats: &'a mut HashMap<String, PlayerStats>, player: &str) -> &'a mut PlayerStats {
+    stats.entry(player.to_string()).or_insert_with(|| PlayerStats {
+        player: player.to_string(),
+        ..PlayerStats::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::RecordStore;
+
+    #[test]
+    fn records_placements_and_time_held_are_counted() {
+        let mut store: RecordStore = serde_json::from_str(
+            r#"{
+                "ctt2_mode": true,
+                "records": [
+                    { "player": "amy", "time": 12.0, "mode": "Bounce", "obby": "Lava Tower", "submitted": 100 },
+                    { "player": "bob", "time": 11.0, "mode": "Bounce", "obby": "Lava Tower", "submitted": 400 },
+                    { "player": "amy", "time": 11.5, "mode": "Bounce", "obby": "Lava Tower", "submitted": 500 },
+                    { "player": "amy", "time": 20.0, "mode": "Bounceless", "obby": "Lava Tower", "submitted": 600 },
+                    { "player": "cat", "time": 30.0, "mode": "Bounce", "obby": "Ice Cave", "submitted": 900 }
+                ],
+                "main_obby": { "NoPlat": [["cat", 90.0], ["amy", 95.0]] }
+            }"#,
+        )
+        .unwrap();
+        store.finish_load();
+
+        let stats = store.player_stats(1000);
+        let players: Vec<&str> = stats.iter().map(|s| s.player.as_str()).collect();
+        // Ties on records go to Main Obby slots, then time held.
+        assert_eq!(players, ["amy", "cat", "bob"]);
+
+        let amy = &stats[0];
+        assert_eq!(amy.records(), 1);
+        assert_eq!(amy.records_by_mode.get("Bounceless"), Some(&1));
+        assert_eq!(amy.main_obby, [("NoPlat".to_string(), 1)]);
+        // 100..400 on Bounce, 600..1000 on Bounceless.
+        assert_eq!(amy.held_secs, 300 + 400);
+
+        assert_eq!(stats[1].main_obby, [("NoPlat".to_string(), 0)]);
+        assert_eq!(stats[2].held_secs, 600);
+        assert_eq!(
+            amy.describe(),
+            "amy - 1 record (Bounceless 1) - Main Obby: NoPlat #2 - held 11m"
+        );
+    }
+
+    #[test]
+    fn legacy_runs_without_a_submission_time_add_no_time_held() {
+        let mut store: RecordStore = serde_json::from_str(
+            r#"{
+                "ctt2_mode": true,
+                "records": [
+                    { "player": "amy", "time": 12.0, "mode": "Bounce", "obby": "Lava Tower" },
+                    { "player": "bob", "time": 11.0, "mode": "Bounce", "obby": "Lava Tower", "submitted": 400 },
+                    { "player": "cat", "time": 30.0, "mode": "Bounce", "obby": "Ice Cave" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        store.finish_load();
+
+        let stats = store.player_stats(1000);
+        let held = |player: &str| stats.iter().find(|s| s.player == player).unwrap().held_secs;
+        assert_eq!(held("amy"), 0);
+        assert_eq!(held("bob"), 600);
+        // Still the record holder, but since an unknown time.
+        assert_eq!(held("cat"), 0);
+        assert_eq!(stats.iter().find(|s| s.player == "cat").unwrap().describe(), "cat - 1 record (Bounce 1)");
+    }
+}
@@ -0,0 +1,2053 @@
+use crate::history::{Change, History};
+use crate::time::Time;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A Main Obby leaderboard. `tag` names its folder under `MISC.LBS.MO` in
+/// Studio and `cap` is the number of slots on the board.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MainObbyCategory {
+    pub name: String,
+    pub tag: String,
+    pub cap: usize,
+}
+
+impl MainObbyCategory {
+    pub fn new(name: &str, tag: &str, cap: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            tag: tag.to_string(),
+            cap,
+        }
+    }
+
+    /// The leaderboards of the original CTT2 map.
+    pub fn defaults() -> Vec<MainObbyCategory> {
+        vec![
+            Self::new("Bounce", "B", 12),
+            Self::new("Bounceless", "NB", 11),
+            Self::new("NoPlat", "NT", 10),
+        ]
+    }
+}
+
+/// A way of running an obby that has its own record, e.g. Bounce. `tag` names
+/// the leaderboard folder inside an obby's folder in CTT2 mode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObbyMode {
+    pub name: String,
+    pub tag: String,
+}
+
+impl ObbyMode {
+    pub fn new(name: &str, tag: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            tag: tag.to_string(),
+        }
+    }
+
+    pub fn defaults() -> Vec<ObbyMode> {
+        vec![Self::new("Bounce", "B"), Self::new("Bounceless", "NB")]
+    }
+}
+
+/// Which differences between two obby names still make them the same obby.
+/// CTT2 mode finds an obby's folder by its upper-cased name, so case is
+/// ignored by default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObbyIdentity {
+    pub ignore_case: bool,
+    pub ignore_whitespace: bool,
+    pub ignore_punctuation: bool,
+}
+
+impl Default for ObbyIdentity {
+    fn default() -> Self {
+        Self {
+            ignore_case: true,
+            ignore_whitespace: false,
+            ignore_punctuation: false,
+        }
+    }
+}
+
+impl ObbyIdentity {
+    /// The part of a name that identifies the obby; names with equal keys are
+    /// the same obby.
+    pub fn key(&self, name: &str) -> String {
+        let key: String = name
+            .trim()
+            .chars()
+            .filter(|c| !(self.ignore_whitespace && c.is_whitespace()))
+            .filter(|c| !(self.ignore_punctuation && c.is_ascii_punctuation()))
+            .collect();
+        if self.ignore_case { key.to_lowercase() } else { key }
+    }
+}
+
+/// A player with their other usernames. Runs and Main Obby entries hold the
+/// canonical `name`; new entries under an alias are stored under it too.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Player {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub roblox_id: Option<u64>,
+}
+
+impl Player {
+    /// Roblox usernames ignore case, so aliases do too.
+    fn is_called(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.aliases.iter().any(|a| a.eq_ignore_ascii_case(name))
+    }
+}
+
+/// One run on an obby. Every run is kept; the record holder of an obby/mode is
+/// whichever run is fastest. On equal times the earlier submission wins.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredRecord")]
+pub struct Record {
+    pub player: String,
+    pub time: Time,
+    pub mode: String,
+    pub obby: String,
+    /// Seconds since the Unix epoch when the run was entered.
+    pub submitted: u64,
+}
+
+impl Record {
+    pub fn is_placeholder(&self) -> bool {
+        is_placeholder(&self.player, self.time)
+    }
+}
+
+/// Whether a run or leaderboard entry looks like an unreadable entry that an
+/// older import kept with an empty name or the 9999s stand-in time.
+pub fn is_placeholder(player: &str, time: Time) -> bool {
+    player.trim().is_empty() || time.is_placeholder()
+}
+
+/// Top-level keys of the export that sit next to the obby names, so Studio
+/// and imports would read an obby of that name as something else.
+pub const RESERVED_OBBY_NAMES: [&str; 5] = ["CTT2Mode", "ModeTags", "Boards", "MainObby", "MainObbyTags"];
+
+/// Why `name` cannot name an obby.
+pub fn obby_name_problem(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    if name.is_empty() {
+        Some("obby names cannot be empty")
+    } else if RESERVED_OBBY_NAMES.contains(&name) {
+        Some("that name is a section of the export, not an obby")
+    } else {
+        None
+    }
+}
+
+/// Why Roblox would not accept `name` as a username: it must be 3 to 20
+/// letters, digits and at most one underscore, which may not start or end it.
+pub fn username_problem(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    if !(3..=20).contains(&name.chars().count()) {
+        Some("Roblox usernames are 3 to 20 characters long")
+    } else if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Some("Roblox usernames only use letters, digits and _")
+    } else if name.matches('_').count() > 1 {
+        Some("Roblox usernames have at most one _")
+    } else if name.starts_with('_') || name.ends_with('_') {
+        Some("Roblox usernames cannot start or end with _")
+    } else {
+        None
+    }
+}
+
+/// An edit that was forced through, e.g. to make a time slower, and why.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    /// Where the entry is, e.g. "Lava Tower / Bounce" or "Main Obby / NoPlat".
+    pub target: String,
+    pub before: (String, Time),
+    pub after: (String, Time),
+    pub reason: String,
+    /// Seconds since the Unix epoch.
+    pub at: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A `Record` as sessions store it. Older sessions only had `bounce`.
+#[derive(Deserialize)]
+struct StoredRecord {
+    player: String,
+    time: Time,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    bounce: bool,
+    obby: String,
+    #[serde(default)]
+    submitted: u64,
+}
+
+impl From<StoredRecord> for Record {
+    fn from(stored: StoredRecord) -> Self {
+        let mode = stored
+            .mode
+            .unwrap_or_else(|| if stored.bounce { "Bounce" } else { "Bounceless" }.to_string());
+        Record {
+            player: stored.player,
+            time: stored.time,
+            mode,
+            obby: stored.obby,
+            submitted: stored.submitted,
+        }
+    }
+}
+
+/// What happened to a run handed to `RecordStore::add_record_entry`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AddOutcome {
+    /// First run for this obby/mode.
+    Added,
+    /// Faster than the previous holder, who is returned.
+    Improved(Record),
+    /// Kept in the history, but the current holder, returned, is faster.
+    Slower(Record),
+    /// The exact same run is already stored.
+    Duplicate,
+    UnknownMode,
+    /// The obby name cannot be used, for the reason given.
+    BadObbyName(&'static str),
+}
+
+/// What happened to an entry handed to `RecordStore::add_main_ob_record`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MainObbyOutcome {
+    /// Entered the leaderboard at this 0-based place.
+    Placed(usize),
+    /// Replaced the player's own slower entry, whose time is returned.
+    Improved { place: usize, previous: Time },
+    /// The exact same entry is already on the leaderboard or in the archive.
+    Duplicate,
+    /// The player already holds a slot with this faster or equal time.
+    SlowerThanOwn(Time),
+    /// Did not make the cut and went to the archive.
+    TooSlow,
+    UnknownCategory,
+}
+
+/// Whether one player may hold more than one slot on a Main Obby leaderboard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MainObbyRule {
+    /// Only a player's best time is listed.
+    #[default]
+    BestPerPlayer,
+    /// Every run is listed, as long as it is not an exact duplicate.
+    AllowMultiple,
+}
+
+/// All records of a session plus the rules for merging new ones in. The GUI,
+/// the command line and the HTTP server all work on this.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordStore {
+    ctt2_mode: bool,
+    records: Vec<Record>,
+    /// Obby modes in the order they are shown and exported.
+    #[serde(default = "ObbyMode::defaults")]
+    modes: Vec<ObbyMode>,
+    /// Main Obby leaderboards in the order they are shown and exported.
+    #[serde(default = "MainObbyCategory::defaults")]
+    main_obby_categories: Vec<MainObbyCategory>,
+    /// Every Main Obby entry, fastest first. The first `cap` entries are the
+    /// leaderboard, the rest are archived until a deletion makes room.
+    main_obby: BTreeMap<String, Vec<(String, Time)>>,
+    obby_names: HashSet<String>,
+    main_obby_rule: MainObbyRule,
+    /// Obbies with a ranked board, and how many places it has.
+    boards: BTreeMap<String, usize>,
+    /// Names shown in the app instead of the Studio folder name.
+    display_names: BTreeMap<String, String>,
+    obby_identity: ObbyIdentity,
+    /// Players with aliases or a Roblox ID. Other players only exist as the
+    /// names on their entries.
+    players: Vec<Player>,
+    corrections: Vec<Correction>,
+
+    // Sessions from before the categories were configurable.
+    #[serde(skip_serializing)]
+    main_ob_bounce: Vec<(String, Time)>,
+    #[serde(skip_serializing)]
+    main_ob_bounceless: Vec<(String, Time)>,
+    #[serde(skip_serializing)]
+    main_ob_noplat: Vec<(String, Time)>,
+
+    #[serde(skip)]
+    history: History,
+    #[serde(skip)]
+    revision: u64,
+}
+
+impl RecordStore {
+    pub fn new() -> Self {
+        Self {
+            modes: ObbyMode::defaults(),
+            main_obby_categories: MainObbyCategory::defaults(),
+            ..Self::default()
+        }
+    }
+
+    /// A copy of the records without the undo history, for trying changes out.
+    pub(crate) fn scratch_copy(&self) -> RecordStore {
+        RecordStore {
+            ctt2_mode: self.ctt2_mode,
+            records: self.records.clone(),
+            modes: self.modes.clone(),
+            main_obby_categories: self.main_obby_categories.clone(),
+            main_obby: self.main_obby.clone(),
+            obby_names: self.obby_names.clone(),
+            boards: self.boards.clone(),
+            display_names: self.display_names.clone(),
+            obby_identity: self.obby_identity,
+            players: self.players.clone(),
+            corrections: self.corrections.clone(),
+            main_obby_rule: self.main_obby_rule,
+            ..Default::default()
+        }
+    }
+
+    /// Fills in derived state after the store has been deserialized.
+    pub(crate) fn finish_load(&mut self) {
+        self.obby_names
+            .extend(self.records.iter().map(|r| r.obby.clone()));
+
+        // Hand-edited sessions may use modes that are not configured.
+        for record in &self.records {
+            if !self.modes.iter().any(|m| m.name == record.mode) {
+                self.modes.push(ObbyMode::new(&record.mode, &record.mode));
+            }
+        }
+
+        let legacy = [
+            ("Bounce", std::mem::take(&mut self.main_ob_bounce)),
+            ("Bounceless", std::mem::take(&mut self.main_ob_bounceless)),
+            ("NoPlat", std::mem::take(&mut self.main_ob_noplat)),
+        ];
+        for (category, list) in legacy {
+            if !list.is_empty() {
+                self.main_obby.entry(category.to_string()).or_default().extend(list);
+            }
+        }
+
+        // Older sessions could hold the same entry many times over.
+        let rule = self.main_obby_rule;
+        for list in self.main_obby.values_mut() {
+            *list = Self::normalized(list, rule);
+        }
+    }
+
+    /// Increases with every change, so callers can tell when to save or
+    /// republish the records.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn touch(&mut self) {
+        self.revision += 1;
+    }
+
+    pub fn ctt2_mode(&self) -> bool {
+        self.ctt2_mode
+    }
+
+    pub fn set_ctt2_mode(&mut self, ctt2_mode: bool) {
+        if self.ctt2_mode != ctt2_mode {
+            self.ctt2_mode = ctt2_mode;
+            self.touch();
+        }
+    }
+
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Every known obby, sorted by name ignoring case.
+    pub fn obby_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.obby_names.iter().collect();
+        names.sort_by_cached_key(|name| (name.to_lowercase(), name.to_string()));
+        names
+    }
+
+    pub fn obby_identity(&self) -> ObbyIdentity {
+        self.obby_identity
+    }
+
+    /// Changes which obby names count as the same obby. Obbies that now
+    /// collide are left alone; `obby_collisions` lists them for merging.
+    pub fn set_obby_identity(&mut self, identity: ObbyIdentity) {
+        if self.obby_identity == identity {
+            return;
+        }
+
+        self.history.begin("Change obby matching");
+        self.history.push(Change::ObbyIdentityChanged {
+            old: self.obby_identity,
+            new: identity,
+        });
+        self.history.commit();
+        self.obby_identity = identity;
+        self.touch();
+    }
+
+    /// The known obby that `name` refers to: the exact name if it is known,
+    /// else one that is the same obby under the identity rule.
+    pub fn find_obby(&self, name: &str) -> Option<&String> {
+        if let Some(exact) = self.obby_names.get(name) {
+            return Some(exact);
+        }
+        let key = self.obby_identity.key(name);
+        self.obby_names().into_iter().find(|obby| self.obby_identity.key(obby) == key)
+    }
+
+    /// The name a new run on `name` is stored under: the known obby it matches,
+    /// or the trimmed name itself.
+    fn canonical_obby(&self, name: &str) -> String {
+        self.find_obby(name).cloned().unwrap_or_else(|| name.trim().to_string())
+    }
+
+    /// Groups of known obbies that are the same obby under the identity rule,
+    /// e.g. from sessions made before the rule or after changing it.
+    pub fn obby_collisions(&self) -> Vec<Vec<&String>> {
+        let mut groups: BTreeMap<String, Vec<&String>> = BTreeMap::new();
+        for obby in self.obby_names() {
+            groups.entry(self.obby_identity.key(obby)).or_default().push(obby);
+        }
+        groups.into_values().filter(|group| group.len() > 1).collect()
+    }
+
+    /// The name to show for an obby: its display name if it has one, else the
+    /// Studio folder name.
+    pub fn display_name<'a>(&'a self, obby: &'a str) -> &'a str {
+        self.display_names.get(obby).map_or(obby, String::as_str)
+    }
+
+    /// Sets or, with an empty name, clears the display name of an obby.
+    pub fn set_display_name(&mut self, obby: &str, name: &str) {
+        let name = name.trim();
+        let new = (!name.is_empty() && name != obby).then(|| name.to_string());
+        let old = self.display_names.get(obby).cloned();
+        if old == new {
+            return;
+        }
+
+        self.history.begin("Change display name");
+        self.history.push(Change::DisplayNameChanged {
+            obby: obby.to_string(),
+            old,
+            new: new.clone(),
+        });
+        self.history.commit();
+        self.set_display_name_entry(obby, new);
+        self.touch();
+    }
+
+    fn set_display_name_entry(&mut self, obby: &str, name: Option<String>) {
+        match name {
+            Some(name) => self.display_names.insert(obby.to_string(), name),
+            None => self.display_names.remove(obby),
+        };
+    }
+
+    /// How many runs an obby has, over all modes.
+    pub fn run_count(&self, obby: &str) -> usize {
+        self.records.iter().filter(|r| r.obby == obby).count()
+    }
+
+    /// Renames an obby in every run, its board and its display name.
+    pub fn rename_obby(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let to = to.trim();
+        if let Some(problem) = obby_name_problem(to) {
+            return Err(problem.to_string());
+        }
+        if let Some(existing) = self.find_obby(to)
+            && existing != from
+        {
+            return Err(format!("{} already exists; merge the two instead", existing));
+        }
+        self.move_obby("Rename obby", from, to)
+    }
+
+    /// Moves every run of `from` to `into` and removes `from`. The board and
+    /// display name of `into` win; those of `from` are used if it has none.
+    pub fn merge_obby(&mut self, from: &str, into: &str) -> Result<(), String> {
+        if from == into {
+            return Err("pick two different obbies".to_string());
+        }
+        if !self.obby_names.contains(into) {
+            return Err(format!("unknown obby '{}'", into));
+        }
+        self.move_obby("Merge obbies", from, into)
+    }
+
+    fn move_obby(&mut self, label: &str, from: &str, to: &str) -> Result<(), String> {
+        if !self.obby_names.contains(from) {
+            return Err(format!("unknown obby '{}'", from));
+        }
+        // Nothing moves, so the board, display name and name must stay.
+        if from == to {
+            return Ok(());
+        }
+
+        self.history.begin(label);
+        for index in 0..self.records.len() {
+            if self.records[index].obby != from {
+                continue;
+            }
+            let old = self.records[index].clone();
+            let new = Record {
+                obby: to.to_string(),
+                ..old.clone()
+            };
+            self.history.push(Change::RecordEdited {
+                index,
+                old,
+                new: new.clone(),
+            });
+            self.records[index] = new;
+        }
+
+        if let Some(size) = self.board_size(from) {
+            if self.board_size(to).is_none() {
+                self.history.push(Change::BoardSizeChanged {
+                    obby: to.to_string(),
+                    old: None,
+                    new: Some(size),
+                });
+                self.set_board_entry(to, Some(size));
+            }
+            self.history.push(Change::BoardSizeChanged {
+                obby: from.to_string(),
+                old: Some(size),
+                new: None,
+            });
+            self.set_board_entry(from, None);
+        }
+
+        if let Some(name) = self.display_names.get(from).cloned() {
+            if !self.display_names.contains_key(to) && name != to {
+                self.history.push(Change::DisplayNameChanged {
+                    obby: to.to_string(),
+                    old: None,
+                    new: Some(name.clone()),
+                });
+                self.set_display_name_entry(to, Some(name.clone()));
+            }
+            self.history.push(Change::DisplayNameChanged {
+                obby: from.to_string(),
+                old: Some(name),
+                new: None,
+            });
+            self.set_display_name_entry(from, None);
+        }
+
+        if self.obby_names.insert(to.to_string()) {
+            self.history.push(Change::ObbyNameAdded(to.to_string()));
+        }
+        self.obby_names.remove(from);
+        self.history.push(Change::ObbyNameRemoved(from.to_string()));
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    /// Every player name on a run or Main Obby entry or in the registry,
+    /// sorted ignoring case.
+    pub fn player_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self
+            .records
+            .iter()
+            .map(|r| &r.player)
+            .chain(self.main_obby.values().flatten().map(|(p, _)| p))
+            .chain(self.players.iter().map(|p| &p.name))
+            .collect();
+        names.sort_by_cached_key(|name| (name.to_lowercase(), name.to_string()));
+        names.dedup();
+        names
+    }
+
+    /// Known players and aliases containing `text`, ignoring case, for
+    /// suggesting names while typing. Each is paired with the canonical name
+    /// it is saved under.
+    pub fn player_suggestions(&self, text: &str, limit: usize) -> Vec<(&String, &String)> {
+        let needle = text.trim().to_lowercase();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let aliases = self
+            .players
+            .iter()
+            .flat_map(|p| p.aliases.iter().map(move |alias| (alias, &p.name)));
+        let mut matches: Vec<(&String, &String)> = self
+            .player_names()
+            .into_iter()
+            .map(|name| (name, name))
+            .chain(aliases)
+            .filter(|(name, _)| name.to_lowercase().contains(&needle) && name.trim() != text.trim())
+            .collect();
+        // Names starting with the text first.
+        matches.sort_by_key(|(name, _)| !name.to_lowercase().starts_with(&needle));
+        matches.truncate(limit);
+        matches
+    }
+
+    /// The registry entry of a player, found by name or alias.
+    pub fn player(&self, name: &str) -> Option<&Player> {
+        self.players.iter().find(|p| p.is_called(name.trim()))
+    }
+
+    /// The canonical name for `name`: the registered player it is an alias
+    /// of, or the trimmed name itself.
+    pub fn resolve_player(&self, name: &str) -> String {
+        match self.player(name) {
+            Some(player) => player.name.clone(),
+            None => name.trim().to_string(),
+        }
+    }
+
+    /// Replaces the aliases and Roblox ID of a player, registering them if
+    /// needed. An alias may not be the name of another player; merge those.
+    pub fn set_player_info(&mut self, name: &str, aliases: Vec<String>, roblox_id: Option<u64>) -> Result<(), String> {
+        let name = self.resolve_player(name);
+        let mut aliases: Vec<String> = aliases
+            .into_iter()
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty() && !a.eq_ignore_ascii_case(&name))
+            .collect();
+        aliases.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+        for alias in &aliases {
+            if let Some(other) = self.player(alias).filter(|p| p.name != name) {
+                return Err(format!("{} is already an alias of {}", alias, other.name));
+            }
+            if let Some(other) = self.player_names().into_iter().find(|p| p.eq_ignore_ascii_case(alias)) {
+                return Err(format!("{} has entries of their own; merge them into {} instead", other, name));
+            }
+        }
+        if let Some(id) = roblox_id
+            && let Some(other) = self.players.iter().find(|p| p.roblox_id == Some(id) && p.name != name)
+        {
+            return Err(format!("Roblox ID {} already belongs to {}", id, other.name));
+        }
+
+        let mut players = self.players.clone();
+        match players.iter_mut().find(|p| p.name == name) {
+            Some(player) => {
+                player.aliases = aliases;
+                player.roblox_id = roblox_id;
+            }
+            None => players.push(Player {
+                name,
+                aliases,
+                roblox_id,
+            }),
+        }
+        players.retain(|p| !p.aliases.is_empty() || p.roblox_id.is_some());
+
+        self.history.begin("Change player");
+        self.set_players(players);
+        self.history.commit();
+        Ok(())
+    }
+
+    /// Renames a player on every entry. The old name becomes an alias, so
+    /// later imports under it still find the player.
+    pub fn rename_player(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let from = self.resolve_player(from);
+        let to = to.trim();
+        if to.is_empty() {
+            return Err("player name is empty".to_string());
+        }
+        if !self.player_names().iter().any(|p| **p == from) {
+            return Err(format!("unknown player '{}'", from));
+        }
+        if let Some(other) = self.player_names().into_iter().find(|p| **p != from && p.eq_ignore_ascii_case(to)) {
+            return Err(format!("{} already exists; merge the two instead", other));
+        }
+        if let Some(other) = self.player(to).filter(|p| p.name != from) {
+            return Err(format!("{} is an alias of {}", to, other.name));
+        }
+
+        let mut player = self.player(&from).cloned().unwrap_or_default();
+        player.name = to.to_string();
+        player.aliases.retain(|a| !a.eq_ignore_ascii_case(to));
+        if !from.eq_ignore_ascii_case(to) {
+            player.aliases.push(from.clone());
+        }
+        let mut players: Vec<Player> = self.players.iter().filter(|p| p.name != from).cloned().collect();
+        players.push(player);
+
+        self.history.begin("Rename player");
+        self.replace_player(&from, to);
+        self.set_players(players);
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    /// Moves every entry of `from` to `into`. The name and aliases of `from`
+    /// become aliases of `into`.
+    pub fn merge_player(&mut self, from: &str, into: &str) -> Result<(), String> {
+        let from = self.resolve_player(from);
+        let into = self.resolve_player(into);
+        if from == into {
+            return Err("pick two different players".to_string());
+        }
+        for name in [&from, &into] {
+            if !self.player_names().contains(&name) {
+                return Err(format!("unknown player '{}'", name));
+            }
+        }
+
+        let old = self.player(&from).cloned().unwrap_or_default();
+        let mut merged = self.player(&into).cloned().unwrap_or_else(|| Player {
+            name: into.clone(),
+            ..Player::default()
+        });
+        merged.roblox_id = match (merged.roblox_id, old.roblox_id) {
+            (Some(a), Some(b)) if a != b => {
+                return Err(format!("{} and {} have different Roblox IDs", from, into));
+            }
+            (a, b) => a.or(b),
+        };
+        merged.aliases.push(from.clone());
+        merged.aliases.extend(old.aliases);
+        let mut players: Vec<Player> = self
+            .players
+            .iter()
+            .filter(|p| p.name != from && p.name != into)
+            .cloned()
+            .collect();
+        players.push(merged);
+
+        self.history.begin("Merge players");
+        self.replace_player(&from, &into);
+        self.set_players(players);
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    /// Writes `to` over every entry of `from`, in any letter case as the
+    /// registry matches names. Main Obby leaderboards are normalized again,
+    /// since `to` may now hold two slots.
+    fn replace_player(&mut self, from: &str, to: &str) {
+        for index in 0..self.records.len() {
+            let player = &self.records[index].player;
+            if !player.eq_ignore_ascii_case(from) || player == to {
+                continue;
+            }
+            let old = self.records[index].clone();
+            let new = Record {
+                player: to.to_string(),
+                ..old.clone()
+            };
+            self.history.push(Change::RecordEdited {
+                index,
+                old,
+                new: new.clone(),
+            });
+            self.records[index] = new;
+        }
+
+        let categories: Vec<String> = self.main_obby.keys().cloned().collect();
+        for category in categories {
+            let old = self.main_obby[&category].clone();
+            if !old.iter().any(|(p, _)| p.eq_ignore_ascii_case(from) && p != to) {
+                continue;
+            }
+            let renamed: Vec<(String, Time)> = old
+                .iter()
+                .map(|(p, t)| (if p.eq_ignore_ascii_case(from) { to.to_string() } else { p.clone() }, *t))
+                .collect();
+            let new = Self::normalized(&renamed, self.main_obby_rule);
+            self.set_main_obby_entries(&category, old, new);
+        }
+    }
+
+    fn set_players(&mut self, players: Vec<Player>) {
+        if players != self.players {
+            let old = std::mem::replace(&mut self.players, players.clone());
+            self.history.push(Change::PlayersChanged { old, new: players });
+            self.touch();
+        }
+    }
+
+    /// Removes an obby without runs from the list, with its board and display
+    /// name.
+    pub fn remove_obby(&mut self, obby: &str) -> Result<(), String> {
+        if !self.obby_names.contains(obby) {
+            return Err(format!("unknown obby '{}'", obby));
+        }
+        let runs = self.run_count(obby);
+        if runs > 0 {
+            return Err(format!("{} still has {} runs", obby, runs));
+        }
+
+        self.history.begin("Remove obby");
+        if let Some(size) = self.board_size(obby) {
+            self.history.push(Change::BoardSizeChanged {
+                obby: obby.to_string(),
+                old: Some(size),
+                new: None,
+            });
+            self.set_board_entry(obby, None);
+        }
+        if let Some(name) = self.display_names.remove(obby) {
+            self.history.push(Change::DisplayNameChanged {
+                obby: obby.to_string(),
+                old: Some(name),
+                new: None,
+            });
+        }
+        self.obby_names.remove(obby);
+        self.history.push(Change::ObbyNameRemoved(obby.to_string()));
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    pub fn modes(&self) -> &[ObbyMode] {
+        &self.modes
+    }
+
+    pub fn mode(&self, name: &str) -> Option<&ObbyMode> {
+        self.modes.iter().find(|m| m.name == name)
+    }
+
+    /// Replaces the obby modes as one undo step. A mode that still has runs
+    /// cannot be removed.
+    pub fn set_modes(&mut self, modes: Vec<ObbyMode>) -> Result<(), String> {
+        let modes = self.checked_modes(modes)?;
+        self.history.begin("Change modes");
+        self.replace_modes(modes);
+        self.history.commit();
+        Ok(())
+    }
+
+    /// Replaces the modes and the Main Obby categories as one undo step,
+    /// changing neither unless both are valid.
+    pub fn set_categories(
+        &mut self,
+        modes: Vec<ObbyMode>,
+        categories: Vec<MainObbyCategory>,
+    ) -> Result<(), String> {
+        let modes = self.checked_modes(modes)?;
+        let categories = Self::checked_main_obby_categories(categories)?;
+        self.history.begin("Change categories");
+        self.replace_modes(modes);
+        self.replace_main_obby_categories(categories);
+        self.history.commit();
+        Ok(())
+    }
+
+    /// Trims the modes and checks them against each other and the runs.
+    fn checked_modes(&self, modes: Vec<ObbyMode>) -> Result<Vec<ObbyMode>, String> {
+        let modes: Vec<ObbyMode> = modes
+            .into_iter()
+            .map(|m| ObbyMode::new(m.name.trim(), m.tag.trim()))
+            .collect();
+
+        for (i, mode) in modes.iter().enumerate() {
+            if mode.name.is_empty() {
+                return Err("mode names cannot be empty".to_string());
+            }
+            if mode.tag.is_empty() {
+                return Err(format!("{} needs a Studio tag", mode.name));
+            }
+            if let Some(other) = modes[..i].iter().find(|m| m.name == mode.name) {
+                return Err(format!("{} is listed twice", other.name));
+            }
+            if let Some(other) = modes[..i].iter().find(|m| m.tag == mode.tag) {
+                return Err(format!("{} and {} share the tag {}", other.name, mode.name, mode.tag));
+            }
+        }
+        if let Some(record) = self.records.iter().find(|r| !modes.iter().any(|m| m.name == r.mode)) {
+            return Err(format!("{} still has runs, e.g. on {}", record.mode, record.obby));
+        }
+        Ok(modes)
+    }
+
+    fn replace_modes(&mut self, modes: Vec<ObbyMode>) {
+        if modes == self.modes {
+            return;
+        }
+        let old = std::mem::replace(&mut self.modes, modes.clone());
+        self.history.push(Change::ModesChanged { old, new: modes });
+        self.touch();
+    }
+
+    pub fn main_obby_categories(&self) -> &[MainObbyCategory] {
+        &self.main_obby_categories
+    }
+
+    pub fn main_obby_category(&self, name: &str) -> Option<&MainObbyCategory> {
+        self.main_obby_categories.iter().find(|c| c.name == name)
+    }
+
+    /// Replaces the Main Obby categories as one undo step. Entries of removed
+    /// categories and those below a lowered cap stay archived, so adding the
+    /// category back or raising the cap brings them back.
+    pub fn set_main_obby_categories(&mut self, categories: Vec<MainObbyCategory>) -> Result<(), String> {
+        let categories = Self::checked_main_obby_categories(categories)?;
+        self.history.begin("Change Main Obby categories");
+        self.replace_main_obby_categories(categories);
+        self.history.commit();
+        Ok(())
+    }
+
+    /// Trims the categories and checks them against each other.
+    fn checked_main_obby_categories(categories: Vec<MainObbyCategory>) -> Result<Vec<MainObbyCategory>, String> {
+        let categories: Vec<MainObbyCategory> = categories
+            .into_iter()
+            .map(|c| MainObbyCategory::new(c.name.trim(), c.tag.trim(), c.cap))
+            .collect();
+
+        for (i, category) in categories.iter().enumerate() {
+            if category.name.is_empty() {
+                return Err("category names cannot be empty".to_string());
+            }
+            if category.tag.is_empty() {
+                return Err(format!("{} needs a Studio tag", category.name));
+            }
+            if category.cap == 0 {
+                return Err(format!("{} needs at least one slot", category.name));
+            }
+            if let Some(other) = categories[..i].iter().find(|c| c.name == category.name) {
+                return Err(format!("{} is listed twice", other.name));
+            }
+            if let Some(other) = categories[..i].iter().find(|c| c.tag == category.tag) {
+                return Err(format!(
+                    "{} and {} share the tag {}",
+                    other.name, category.name, category.tag
+                ));
+            }
+        }
+        Ok(categories)
+    }
+
+    fn replace_main_obby_categories(&mut self, categories: Vec<MainObbyCategory>) {
+        if categories == self.main_obby_categories {
+            return;
+        }
+        let old = std::mem::replace(&mut self.main_obby_categories, categories.clone());
+        self.history.push(Change::MainObbyCategoriesChanged { old, new: categories });
+        self.touch();
+    }
+
+    /// The leaderboard of a configured category, fastest first.
+    pub fn main_obby(&self, category: &str) -> Option<&[(String, Time)]> {
+        let cap = self.main_obby_category(category)?.cap;
+        let list = self.main_obby.get(category).map(Vec::as_slice).unwrap_or_default();
+        Some(&list[..list.len().min(cap)])
+    }
+
+    /// Entries of a configured category that did not fit on the leaderboard,
+    /// fastest first. Their places continue after the leaderboard's.
+    pub fn main_obby_archive(&self, category: &str) -> Option<&[(String, Time)]> {
+        let cap = self.main_obby_category(category)?.cap;
+        let list = self.main_obby.get(category).map(Vec::as_slice).unwrap_or_default();
+        Some(&list[list.len().min(cap)..])
+    }
+
+    /// The leaderboard and archive of a configured category together.
+    fn main_obby_entries(&self, category: &str) -> Option<Vec<(String, Time)>> {
+        self.main_obby_category(category)?;
+        Some(self.main_obby.get(category).cloned().unwrap_or_default())
+    }
+
+    /// Replaces every entry of a category and records the change.
+    fn set_main_obby_entries(&mut self, category: &str, old: Vec<(String, Time)>, new: Vec<(String, Time)>) {
+        self.history.push(Change::MainObbyChanged {
+            category: category.to_string(),
+            old,
+            new: new.clone(),
+        });
+        self.main_obby.insert(category.to_string(), new);
+    }
+
+    pub fn main_obby_rule(&self) -> MainObbyRule {
+        self.main_obby_rule
+    }
+
+    /// Changes the rule. Switching to `BestPerPlayer` drops every entry but
+    /// each player's best, as one undo step.
+    pub fn set_main_obby_rule(&mut self, rule: MainObbyRule) {
+        if self.main_obby_rule == rule {
+            return;
+        }
+
+        self.history.begin("Change Main Obby rule");
+        self.history.push(Change::MainObbyRuleChanged {
+            old: self.main_obby_rule,
+            new: rule,
+        });
+        self.main_obby_rule = rule;
+        for (category, list) in &mut self.main_obby {
+            let old = list.clone();
+            let new = Self::normalized(list, rule);
+            if new != old {
+                *list = new.clone();
+                self.history.push(Change::MainObbyChanged {
+                    category: category.clone(),
+                    old,
+                    new,
+                });
+            }
+        }
+        self.history.commit();
+        self.touch();
+    }
+
+    /// The list sorted by time without exact duplicates and, under
+    /// `BestPerPlayer`, with only the fastest entry of each player.
+    fn normalized(list: &[(String, Time)], rule: MainObbyRule) -> Vec<(String, Time)> {
+        let mut sorted = list.to_vec();
+        sorted.sort_by_key(|entry| entry.1);
+
+        let mut out: Vec<(String, Time)> = Vec::with_capacity(sorted.len());
+        for entry in sorted {
+            let keep = match rule {
+                MainObbyRule::BestPerPlayer => !out.iter().any(|(p, _)| *p == entry.0),
+                MainObbyRule::AllowMultiple => !out.contains(&entry),
+            };
+            if keep {
+                out.push(entry);
+            }
+        }
+        out
+    }
+
+    pub fn add_record_entry(&mut self, obby: &str, mode: &str, player: &str, time: Time) -> AddOutcome {
+        if self.mode(mode).is_none() {
+            return AddOutcome::UnknownMode;
+        }
+        if let Some(problem) = obby_name_problem(obby) {
+            return AddOutcome::BadObbyName(problem);
+        }
+
+        let obby = self.canonical_obby(obby);
+        let new_record = Record {
+            player: self.resolve_player(player),
+            time,
+            mode: mode.to_string(),
+            obby: obby.clone(),
+            submitted: now(),
+        };
+
+        // track it
+        if self.obby_names.insert(obby.clone()) {
+            self.history.push(Change::ObbyNameAdded(obby));
+            self.touch();
+        }
+
+        // Importing the same data twice must not fill the history with copies.
+        let duplicate = self.records.iter().any(|r| {
+            r.obby == new_record.obby
+                && r.mode == new_record.mode
+                && r.player == new_record.player
+                && r.time == new_record.time
+        });
+        if duplicate {
+            return AddOutcome::Duplicate;
+        }
+
+        let holder = self
+            .current_records()
+            .find(|r| r.obby == new_record.obby && r.mode == new_record.mode)
+            .cloned();
+        let outcome = match holder {
+            None => AddOutcome::Added,
+            Some(holder) if new_record.time < holder.time => AddOutcome::Improved(holder),
+            Some(holder) => AddOutcome::Slower(holder),
+        };
+
+        self.history.push(Change::RecordInserted {
+            index: self.records.len(),
+            record: new_record.clone(),
+        });
+        self.records.push(new_record);
+        self.touch();
+        outcome
+    }
+
+    /// Orders runs by time, then by submission, then by position, so equal
+    /// times always go to the run that was entered first.
+    fn rank_key(&self, index: usize) -> (Time, u64, usize) {
+        let run = &self.records[index];
+        (run.time, run.submitted, index)
+    }
+
+    /// Indices of the fastest run for every obby/mode, in the order the
+    /// obby/mode was first seen.
+    pub fn current_record_indices(&self) -> Vec<usize> {
+        let mut best: Vec<usize> = Vec::new();
+        // obby/mode -> position in `best`, so thousands of runs stay cheap.
+        let mut slots: HashMap<(&str, &str), usize> = HashMap::new();
+
+        for (i, r) in self.records.iter().enumerate() {
+            match slots.get(&(r.obby.as_str(), r.mode.as_str())) {
+                Some(&slot) => {
+                    if self.rank_key(i) < self.rank_key(best[slot]) {
+                        best[slot] = i;
+                    }
+                }
+                None => {
+                    slots.insert((&r.obby, &r.mode), best.len());
+                    best.push(i);
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The record holder of every obby/mode.
+    pub fn current_records(&self) -> impl Iterator<Item = &Record> {
+        self.current_record_indices()
+            .into_iter()
+            .map(|i| &self.records[i])
+    }
+
+    pub fn board_size(&self, obby: &str) -> Option<usize> {
+        self.boards.get(obby).copied()
+    }
+
+    /// Gives an obby a ranked board with `size` places, or removes it with
+    /// `None`. The obby is looked up like a new run's, so "lava tower" finds
+    /// "Lava Tower".
+    pub fn set_board_size(&mut self, obby: &str, size: Option<usize>) -> Result<(), String> {
+        let Some(obby) = self.find_obby(obby).cloned() else {
+            return Err(format!("unknown obby '{}'", obby.trim()));
+        };
+        let size = size.filter(|&n| n > 0);
+        let old = self.board_size(&obby);
+        if old == size {
+            return Ok(());
+        }
+
+        self.history.begin("Change board size");
+        self.history.push(Change::BoardSizeChanged {
+            obby: obby.clone(),
+            old,
+            new: size,
+        });
+        self.history.commit();
+        self.set_board_entry(&obby, size);
+        self.touch();
+        Ok(())
+    }
+
+    fn set_board_entry(&mut self, obby: &str, size: Option<usize>) {
+        match size {
+            Some(size) => self.boards.insert(obby.to_string(), size),
+            None => self.boards.remove(obby),
+        };
+    }
+
+    /// The ranked board of an obby/mode: each player's best run, fastest
+    /// first. The Main Obby rule does not apply here, so allowing several
+    /// Main Obby entries per player leaves these boards alone. Empty if the
+    /// obby has no board.
+    pub fn board(&self, obby: &str, mode: &str) -> Vec<(String, Time)> {
+        let Some(size) = self.board_size(obby) else {
+            return Vec::new();
+        };
+
+        let runs: Vec<(String, Time)> = self
+            .runs_for(obby, mode)
+            .into_iter()
+            .map(|i| (self.records[i].player.clone(), self.records[i].time))
+            .collect();
+        let mut board = Self::normalized(&runs, MainObbyRule::BestPerPlayer);
+        board.truncate(size);
+        board
+    }
+
+    /// Runs and Main Obby entries that look like placeholders and should be
+    /// checked and deleted.
+    pub fn placeholder_count(&self) -> usize {
+        let runs = self.records.iter().filter(|r| r.is_placeholder()).count();
+        let main_obby = self
+            .main_obby
+            .values()
+            .flatten()
+            .filter(|(p, t)| is_placeholder(p, *t))
+            .count();
+        runs + main_obby
+    }
+
+    /// All runs of an obby/mode, fastest first.
+    pub fn runs_for(&self, obby: &str, mode: &str) -> Vec<usize> {
+        let mut runs: Vec<usize> = (0..self.records.len())
+            .filter(|&i| self.records[i].obby == obby && self.records[i].mode == mode)
+            .collect();
+        runs.sort_by_key(|&i| self.rank_key(i));
+        runs
+    }
+
+    /// Adds an entry to a Main Obby leaderboard, keeping it sorted. Entries
+    /// beyond the cap are archived. Adding an entry that is already listed
+    /// changes nothing. An entry tying an existing one is placed after it.
+    pub fn add_main_ob_record(&mut self, player: String, time: Time, category: &str) -> MainObbyOutcome {
+        let Some(cap) = self.main_obby_category(category).map(|c| c.cap) else {
+            return MainObbyOutcome::UnknownCategory;
+        };
+        let rule = self.main_obby_rule;
+        let Some(mut list) = self.main_obby_entries(category) else {
+            return MainObbyOutcome::UnknownCategory;
+        };
+        let player = self.resolve_player(&player);
+
+        if list.iter().any(|(p, t)| *p == player && *t == time) {
+            return MainObbyOutcome::Duplicate;
+        }
+
+        let own = list.iter().position(|(p, _)| *p == player);
+        let mut previous = None;
+        if rule == MainObbyRule::BestPerPlayer
+            && let Some(own) = own
+        {
+            if list[own].1 <= time {
+                return MainObbyOutcome::SlowerThanOwn(list[own].1);
+            }
+            previous = Some(list[own].1);
+        }
+
+        let old = list.clone();
+        let entry = (player, time);
+
+        if let Some(own) = own
+            && previous.is_some()
+        {
+            list.remove(own);
+        }
+        list.push(entry.clone());
+        list.sort_by_key(|entry| entry.1);
+        let place = list.iter().position(|e| *e == entry).unwrap_or(list.len());
+
+        self.set_main_obby_entries(category, old, list);
+        self.touch();
+
+        match previous {
+            _ if place >= cap => MainObbyOutcome::TooSlow,
+            Some(previous) => MainObbyOutcome::Improved { place, previous },
+            None => MainObbyOutcome::Placed(place),
+        }
+    }
+
+    pub fn corrections(&self) -> &[Correction] {
+        &self.corrections
+    }
+
+    fn log_correction(&mut self, target: String, before: (String, Time), after: (String, Time), reason: &str) {
+        let correction = Correction {
+            target,
+            before,
+            after,
+            reason: reason.to_string(),
+            at: now(),
+        };
+        self.history.push(Change::CorrectionLogged(correction.clone()));
+        self.corrections.push(correction);
+    }
+
+    /// Changes a run's player, time, obby or mode; `submitted` is kept. Making
+    /// the time slower needs a reason, which is logged with the correction.
+    pub fn edit_record(&mut self, index: usize, edited: Record, reason: &str) -> Result<(), String> {
+        let Some(old) = self.records.get(index).cloned() else {
+            return Err("that run no longer exists".to_string());
+        };
+        let new = Record {
+            player: self.resolve_player(&edited.player),
+            obby: self.canonical_obby(&edited.obby),
+            submitted: old.submitted,
+            ..edited
+        };
+        let reason = reason.trim();
+
+        if new.player.is_empty() {
+            return Err("player name is empty".to_string());
+        }
+        if let Some(problem) = obby_name_problem(&new.obby) {
+            return Err(problem.to_string());
+        }
+        if self.mode(&new.mode).is_none() {
+            return Err(format!("unknown mode '{}'", new.mode));
+        }
+        if new.time > old.time && reason.is_empty() {
+            return Err(format!(
+                "{}s is slower than {}s; give a reason to force the correction",
+                new.time, old.time
+            ));
+        }
+        if new == old {
+            return Ok(());
+        }
+
+        self.history.begin("Edit record");
+        if self.obby_names.insert(new.obby.clone()) {
+            self.history.push(Change::ObbyNameAdded(new.obby.clone()));
+        }
+        if !reason.is_empty() {
+            self.log_correction(
+                format!("{} / {}", new.obby, new.mode),
+                (old.player.clone(), old.time),
+                (new.player.clone(), new.time),
+                reason,
+            );
+        }
+        self.history.push(Change::RecordEdited {
+            index,
+            old,
+            new: new.clone(),
+        });
+        self.records[index] = new;
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    /// Changes the player or time of a Main Obby entry and re-sorts the
+    /// leaderboard. Places past the cap are archived entries. Like
+    /// `edit_record`, a slower time needs a reason.
+    pub fn edit_main_obby_entry(
+        &mut self,
+        category: &str,
+        place: usize,
+        player: &str,
+        time: Time,
+        reason: &str,
+    ) -> Result<(), String> {
+        let Some(old) = self.main_obby_entries(category) else {
+            return Err(format!("unknown Main Obby category '{}'", category));
+        };
+        let Some(before) = old.get(place).cloned() else {
+            return Err("that entry no longer exists".to_string());
+        };
+        let player = self.resolve_player(player);
+        let player = player.as_str();
+        let reason = reason.trim();
+
+        if player.is_empty() {
+            return Err("player name is empty".to_string());
+        }
+        if time > before.1 && reason.is_empty() {
+            return Err(format!(
+                "{}s is slower than {}s; give a reason to force the correction",
+                time, before.1
+            ));
+        }
+        if self.main_obby_rule == MainObbyRule::BestPerPlayer
+            && old.iter().enumerate().any(|(i, (p, _))| i != place && p == player)
+        {
+            return Err(format!("{} is already listed in {}", player, category));
+        }
+
+        let after = (player.to_string(), time);
+        let mut new = old.clone();
+        new[place] = after.clone();
+        new.sort_by_key(|entry| entry.1);
+        if new == old {
+            return Ok(());
+        }
+
+        self.history.begin("Edit Main Obby entry");
+        if !reason.is_empty() {
+            self.log_correction(format!("Main Obby / {}", category), before, after, reason);
+        }
+        self.set_main_obby_entries(category, old, new);
+        self.history.commit();
+        self.touch();
+        Ok(())
+    }
+
+    /// Removes a Main Obby entry. The best archived entry moves up to fill the
+    /// freed slot.
+    pub fn delete_main_obby_entry(&mut self, category: &str, place: usize) {
+        let Some(old) = self.main_obby_entries(category) else {
+            return;
+        };
+        if place >= old.len() {
+            return;
+        }
+        let mut new = old.clone();
+        new.remove(place);
+
+        self.history.begin("Delete Main Obby entry");
+        self.set_main_obby_entries(category, old, new);
+        self.history.commit();
+        self.touch();
+    }
+
+    pub fn delete_record(&mut self, index: usize) {
+        let record = self.records.remove(index);
+        self.history.begin("Delete record");
+        self.history.push(Change::RecordRemoved { index, record });
+        self.history.commit();
+        self.touch();
+    }
+
+    /// Groups every change until `commit` into one undo step.
+    pub fn begin(&mut self, label: &str) {
+        self.history.begin(label);
+    }
+
+    pub fn commit(&mut self) {
+        self.history.commit();
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.history.undo_label()
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.history.redo_label()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(command) = self.history.undo() else {
+            return false;
+        };
+        for change in command.changes.iter().rev() {
+            self.apply_change(change, true);
+        }
+        self.touch();
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(command) = self.history.redo() else {
+            return false;
+        };
+        for change in &command.changes {
+            self.apply_change(change, false);
+        }
+        self.touch();
+        true
+    }
+
+    fn apply_change(&mut self, change: &Change, undo: bool) {
+        match change {
+            Change::RecordInserted { index, record } | Change::RecordRemoved { index, record } => {
+                let inserting = matches!(change, Change::RecordInserted { .. }) != undo;
+                if inserting {
+                    self.records.insert(*index, record.clone());
+                } else {
+                    self.records.remove(*index);
+                }
+            }
+            Change::MainObbyChanged { category, old, new } => {
+                let list = if undo { old.clone() } else { new.clone() };
+                self.main_obby.insert(category.clone(), list);
+            }
+            Change::ModesChanged { old, new } => {
+                self.modes = if undo { old.clone() } else { new.clone() };
+            }
+            Change::MainObbyCategoriesChanged { old, new } => {
+                self.main_obby_categories = if undo { old.clone() } else { new.clone() };
+            }
+            Change::MainObbyRuleChanged { old, new } => {
+                self.main_obby_rule = if undo { *old } else { *new };
+            }
+            Change::RecordEdited { index, old, new } => {
+                self.records[*index] = if undo { old.clone() } else { new.clone() };
+            }
+            Change::CorrectionLogged(correction) => {
+                if undo {
+                    self.corrections.pop();
+                } else {
+                    self.corrections.push(correction.clone());
+                }
+            }
+            Change::BoardSizeChanged { obby, old, new } => {
+                self.set_board_entry(obby, if undo { *old } else { *new });
+            }
+            Change::ObbyNameAdded(name) | Change::ObbyNameRemoved(name) => {
+                let adding = matches!(change, Change::ObbyNameAdded(_)) != undo;
+                if adding {
+                    self.obby_names.insert(name.clone());
+                } else {
+                    self.obby_names.remove(name);
+                }
+            }
+            Change::PlayersChanged { old, new } => {
+                self.players = if undo { old.clone() } else { new.clone() };
+            }
+            Change::ObbyIdentityChanged { old, new } => {
+                self.obby_identity = if undo { *old } else { *new };
+            }
+            Change::DisplayNameChanged { obby, old, new } => {
+                let name = if undo { old.clone() } else { new.clone() };
+                self.set_display_name_entry(obby, name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: f64) -> Time {
+        Time::from_secs_f64(seconds).unwrap()
+    }
+
+    fn holder(store: &RecordStore, obby: &str, mode: &str) -> Option<(String, Time)> {
+        store
+            .current_records()
+            .find(|r| r.obby == obby && r.mode == mode)
+            .map(|r| (r.player.clone(), r.time))
+    }
+
+    #[test]
+    fn faster_time_wins() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "bob", secs(11.0));
+
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("bob".to_string(), secs(11.0))));
+    }
+
+    #[test]
+    fn slower_time_does_not_take_the_record() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "bob", secs(13.0));
+
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy".to_string(), secs(12.0))));
+        assert_eq!(store.runs_for("Lava Tower", "Bounce").len(), 2);
+    }
+
+    #[test]
+    fn equal_time_keeps_the_earlier_holder() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounceless", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounceless", "bob", secs(12.0));
+
+        assert_eq!(holder(&store, "Lava Tower", "Bounceless"), Some(("amy".to_string(), secs(12.0))));
+    }
+
+    #[test]
+    fn modes_are_tracked_separately() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounceless", "bob", secs(20.0));
+
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy".to_string(), secs(12.0))));
+        assert_eq!(holder(&store, "Lava Tower", "Bounceless"), Some(("bob".to_string(), secs(20.0))));
+    }
+
+    #[test]
+    fn modes_come_from_the_config() {
+        let mut store = RecordStore::new();
+        assert_eq!(
+            store.add_record_entry("Lava Tower", "Glitchless", "amy", secs(30.0)),
+            AddOutcome::UnknownMode
+        );
+
+        assert_eq!(
+            store.add_record_entry("  ", "Bounce", "amy", secs(30.0)),
+            AddOutcome::BadObbyName("obby names cannot be empty")
+        );
+        assert_eq!(
+            store.add_record_entry("Boards", "Bounce", "amy", secs(30.0)),
+            AddOutcome::BadObbyName("that name is a section of the export, not an obby")
+        );
+        assert!(store.obby_names().is_empty());
+
+        let mut modes = store.modes().to_vec();
+        modes.push(ObbyMode::new("Glitchless", "GL"));
+        store.set_modes(modes).unwrap();
+        assert_eq!(
+            store.add_record_entry("Lava Tower", "Glitchless", "amy", secs(30.0)),
+            AddOutcome::Added
+        );
+
+        // The run would be orphaned.
+        assert!(store.set_modes(ObbyMode::defaults()).is_err());
+        assert_eq!(store.modes().len(), 3);
+    }
+
+    #[test]
+    fn records_from_older_sessions_get_a_mode() {
+        let record: Record =
+            serde_json::from_str(r#"{"player":"amy","time":12.0,"bounce":true,"obby":"Lava Tower"}"#).unwrap();
+        assert_eq!(record.mode, "Bounce");
+    }
+
+    #[test]
+    fn deleting_the_record_falls_back_to_the_runner_up() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "bob", secs(11.0));
+
+        let best = store.runs_for("Lava Tower", "Bounce")[0];
+        store.delete_record(best);
+
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy".to_string(), secs(12.0))));
+    }
+
+    #[test]
+    fn board_lists_the_fastest_players() {
+        let mut store = RecordStore::new();
+        for (player, time) in [("amy", secs(12.0)), ("bob", secs(11.0)), ("amy", secs(10.5)), ("cat", secs(13.0)), ("dan", secs(14.0))] {
+            store.add_record_entry("Lava Tower", "Bounce", player, time);
+        }
+        assert!(store.board("Lava Tower", "Bounce").is_empty());
+
+        assert!(store.set_board_size("Nope", Some(3)).is_err());
+        store.set_board_size("lava tower", Some(3)).unwrap();
+        assert_eq!(store.board_size("Lava Tower"), Some(3));
+        assert_eq!(
+            store.board("Lava Tower", "Bounce"),
+            [
+                ("amy".to_string(), secs(10.5)),
+                ("bob".to_string(), secs(11.0)),
+                ("cat".to_string(), secs(13.0))
+            ]
+        );
+
+        // Several Main Obby entries per player do not put amy on twice.
+        store.set_main_obby_rule(MainObbyRule::AllowMultiple);
+        assert_eq!(store.board("Lava Tower", "Bounce")[..2], [("amy".to_string(), secs(10.5)), ("bob".to_string(), secs(11.0))]);
+
+        assert!(store.undo());
+        assert!(store.undo());
+        assert_eq!(store.board_size("Lava Tower"), None);
+    }
+
+    #[test]
+    fn placeholders_from_old_imports_are_counted() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "", secs(11.0));
+        store.add_record_entry("Lava Tower", "Bounceless", "bob", secs(9999.0));
+        store.add_main_ob_record("cat".to_string(), secs(9999.0), "Bounce");
+        // A run of over two hours is slow, not a placeholder.
+        store.add_record_entry("Ice Cave", "Bounce", "dan", secs(10_800.0));
+
+        assert_eq!(store.placeholder_count(), 3);
+        assert!(!store.records()[0].is_placeholder());
+    }
+
+    #[test]
+    fn editing_a_run_to_a_slower_time_needs_a_reason() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "bob", secs(13.0));
+
+        let typo = Record {
+            player: "amy2".to_string(),
+            ..store.records()[0].clone()
+        };
+        store.edit_record(0, typo, "").unwrap();
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy2".to_string(), secs(12.0))));
+
+        let slower = Record {
+            time: secs(14.0),
+            ..store.records()[0].clone()
+        };
+        assert!(store.edit_record(0, slower.clone(), "").is_err());
+        store.edit_record(0, slower, "timer started late").unwrap();
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("bob".to_string(), secs(13.0))));
+        assert_eq!(store.corrections().len(), 1);
+
+        assert!(store.undo());
+        assert!(store.corrections().is_empty());
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy2".to_string(), secs(12.0))));
+    }
+
+    #[test]
+    fn editing_a_main_obby_entry_resorts_the_leaderboard() {
+        let mut store = RecordStore::new();
+        store.add_main_ob_record("amy".to_string(), secs(90.0), "Bounce");
+        store.add_main_ob_record("bob".to_string(), secs(95.0), "Bounce");
+
+        assert!(store.edit_main_obby_entry("Bounce", 0, "bob", secs(90.0), "").is_err());
+        store.edit_main_obby_entry("Bounce", 0, "amy", secs(99.0), "cut a corner").unwrap();
+        assert_eq!(
+            store.main_obby("Bounce").unwrap(),
+            &[("bob".to_string(), secs(95.0)), ("amy".to_string(), secs(99.0))]
+        );
+    }
+
+    #[test]
+    fn merging_obbies_moves_runs_boards_and_names() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Lava-Tower", "Bounce", "bob", secs(11.0));
+        store.set_board_size("Lava-Tower", Some(5)).unwrap();
+        store.set_display_name("Lava-Tower", "The Lava Tower");
+
+        assert!(store.rename_obby("Lava-Tower", "Lava Tower").is_err());
+        store.merge_obby("Lava-Tower", "Lava Tower").unwrap();
+        assert_eq!(store.obby_names(), ["Lava Tower"]);
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("bob".to_string(), secs(11.0))));
+        assert_eq!(store.board_size("Lava Tower"), Some(5));
+        assert_eq!(store.display_name("Lava Tower"), "The Lava Tower");
+
+        assert!(store.undo());
+        assert_eq!(store.obby_names(), ["Lava Tower", "Lava-Tower"]);
+        assert_eq!(store.run_count("Lava-Tower"), 1);
+        assert_eq!(store.board_size("Lava Tower"), None);
+        assert_eq!(store.display_name("Lava-Tower"), "The Lava Tower");
+    }
+
+    #[test]
+    fn obby_names_are_matched_by_the_identity_rule() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("LAVA TOWER ", "Bounce", "bob", secs(11.0));
+        assert_eq!(store.obby_names(), ["Lava Tower"]);
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("bob".to_string(), secs(11.0))));
+
+        store.add_record_entry("Lava-Tower", "Bounce", "cat", secs(10.0));
+        assert_eq!(store.obby_names().len(), 2);
+        assert!(store.obby_collisions().is_empty());
+
+        store.set_obby_identity(ObbyIdentity {
+            ignore_punctuation: true,
+            ignore_whitespace: true,
+            ..ObbyIdentity::default()
+        });
+        assert_eq!(store.obby_collisions(), [["Lava Tower", "Lava-Tower"]]);
+        assert_eq!(store.find_obby("lavatower").map(String::as_str), Some("Lava Tower"));
+        assert!(store.rename_obby("Lava-Tower", "lava tower").is_err());
+        store.rename_obby("Lava Tower", "Lava tower").unwrap();
+    }
+
+    #[test]
+    fn renaming_a_player_updates_every_entry_and_keeps_the_old_name() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_main_ob_record("amy".to_string(), secs(90.0), "Bounce");
+
+        store.rename_player("amy", "amy_2").unwrap();
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy_2".to_string(), secs(12.0))));
+        assert_eq!(store.main_obby("Bounce").unwrap(), &[("amy_2".to_string(), secs(90.0))]);
+        assert_eq!(store.player("AMY").unwrap().name, "amy_2");
+
+        // Imports under the old name land on the renamed player.
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(11.0));
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy_2".to_string(), secs(11.0))));
+        assert_eq!(store.player_names(), ["amy_2"]);
+
+        assert!(store.undo());
+        assert!(store.undo());
+        assert_eq!(store.player_names(), ["amy"]);
+        assert!(store.player("amy").is_none());
+    }
+
+    #[test]
+    fn renaming_a_player_catches_entries_in_other_letter_case() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "Amy", secs(12.0));
+        store.add_record_entry("Ice Cave", "Bounce", "amy", secs(20.0));
+        store.add_main_ob_record("AMY".to_string(), secs(90.0), "Bounce");
+
+        store.rename_player("amy", "amy_2").unwrap();
+        assert_eq!(holder(&store, "Lava Tower", "Bounce"), Some(("amy_2".to_string(), secs(12.0))));
+        assert_eq!(store.main_obby("Bounce").unwrap(), &[("amy_2".to_string(), secs(90.0))]);
+        assert_eq!(store.player_names(), ["amy_2"]);
+    }
+
+    #[test]
+    fn merging_players_keeps_their_best_main_obby_entry() {
+        let mut store = RecordStore::new();
+        store.add_main_ob_record("amy".to_string(), secs(90.0), "Bounce");
+        store.add_main_ob_record("Amy_alt".to_string(), secs(85.0), "Bounce");
+        store.set_player_info("amy", Vec::new(), Some(42)).unwrap();
+        store.set_player_info("Amy_alt", Vec::new(), Some(7)).unwrap();
+        assert!(store.merge_player("Amy_alt", "amy").is_err());
+
+        store.set_player_info("Amy_alt", Vec::new(), None).unwrap();
+        store.merge_player("Amy_alt", "amy").unwrap();
+        assert_eq!(store.main_obby("Bounce").unwrap(), &[("amy".to_string(), secs(85.0))]);
+        assert_eq!(
+            store.player("amy_alt"),
+            Some(&Player {
+                name: "amy".to_string(),
+                aliases: vec!["Amy_alt".to_string()],
+                roblox_id: Some(42),
+            })
+        );
+        assert!(store.set_player_info("bob", vec!["amy".to_string()], None).is_err());
+    }
+
+    #[test]
+    fn usernames_follow_the_roblox_rules() {
+        for good in ["amy", "Builderman", "a_b", "x1234567890123456789"] {
+            assert_eq!(username_problem(good), None, "{}", good);
+        }
+        for bad in ["", "ab", "x12345678901234567890", "a b", "a-b", "a__b", "a_b_c", "_amy", "amy_", "amé"] {
+            assert!(username_problem(bad).is_some(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn suggestions_include_aliases() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "Builderman", secs(12.0));
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(13.0));
+        store.set_player_info("amy", vec!["builder_amy".to_string()], None).unwrap();
+
+        let names = |text: &str| -> Vec<(String, String)> {
+            store
+                .player_suggestions(text, 5)
+                .into_iter()
+                .map(|(name, player)| (name.clone(), player.clone()))
+                .collect()
+        };
+        assert_eq!(
+            names("build"),
+            [
+                ("Builderman".to_string(), "Builderman".to_string()),
+                ("builder_amy".to_string(), "amy".to_string())
+            ]
+        );
+        assert!(names("amy").iter().all(|(name, _)| name != "amy"));
+    }
+
+    #[test]
+    fn obbies_cannot_take_the_name_of_an_export_section() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        assert!(store.rename_obby("Lava Tower", "Boards").is_err());
+        assert!(store.rename_obby("Lava Tower", " MainObby ").is_err());
+        assert!(store.rename_obby("Lava Tower", "ModeTags").is_err());
+
+        let mut moved = store.records()[0].clone();
+        moved.obby = "CTT2Mode".to_string();
+        assert!(store.edit_record(0, moved, "").is_err());
+        assert_eq!(store.obby_names(), ["Lava Tower"]);
+    }
+
+    #[test]
+    fn renaming_an_obby_to_its_own_name_changes_nothing() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.set_board_size("Lava Tower", Some(5)).unwrap();
+        store.set_display_name("Lava Tower", "The Lava Tower");
+        let revision = store.revision();
+
+        store.rename_obby("Lava Tower", " Lava Tower ").unwrap();
+        assert_eq!(store.obby_names(), ["Lava Tower"]);
+        assert_eq!(store.board_size("Lava Tower"), Some(5));
+        assert_eq!(store.display_name("Lava Tower"), "The Lava Tower");
+        assert_eq!(store.revision(), revision);
+    }
+
+    #[test]
+    fn nested_commands_make_one_undo_step() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_record_entry("Ice Cave", "Bounce", "amy", secs(20.0));
+        store.delete_record(1);
+        store.delete_record(0);
+
+        store.begin("Remove unused obbies");
+        store.remove_obby("Lava Tower").unwrap();
+        store.remove_obby("Ice Cave").unwrap();
+        store.commit();
+        assert!(store.obby_names().is_empty());
+        assert_eq!(store.undo_label(), Some("Remove unused obbies"));
+
+        assert!(store.undo());
+        assert_eq!(store.obby_names(), ["Ice Cave", "Lava Tower"]);
+    }
+
+    #[test]
+    fn only_unused_obbies_can_be_removed() {
+        let mut store = RecordStore::new();
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.rename_obby("Lava Tower", "Lava Tower II").unwrap();
+        assert_eq!(store.obby_names(), ["Lava Tower II"]);
+
+        assert!(store.remove_obby("Lava Tower II").is_err());
+        store.delete_record(0);
+        store.remove_obby("Lava Tower II").unwrap();
+        assert!(store.obby_names().is_empty());
+    }
+
+    #[test]
+    fn main_obby_caps() {
+        let mut store = RecordStore::new();
+        for (category, cap) in [("Bounce", 12), ("Bounceless", 11), ("NoPlat", 10)] {
+            for i in 0..20 {
+                store.add_main_ob_record(format!("p{}", i), secs(100.0 - i as f64), category);
+            }
+            let list = store.main_obby(category).unwrap();
+            assert_eq!(list.len(), cap);
+            assert_eq!(list[0], ("p19".to_string(), secs(81.0)));
+            assert!(list.windows(2).all(|w| w[0].1 <= w[1].1));
+        }
+    }
+
+    #[test]
+    fn deleting_a_main_obby_entry_restores_the_next_best() {
+        let mut store = RecordStore::new();
+        for (i, player) in ["amy", "bob", "cat", "dan", "eve", "fay", "gus", "hal", "ivy", "jay", "kim"]
+            .into_iter()
+            .enumerate()
+        {
+            store.add_main_ob_record(player.to_string(), secs(90.0 + i as f64), "NoPlat");
+        }
+        assert_eq!(store.main_obby("NoPlat").unwrap().len(), 10);
+        assert_eq!(store.main_obby_archive("NoPlat").unwrap(), &[("kim".to_string(), secs(100.0))]);
+        assert_eq!(
+            store.add_main_ob_record("kim".to_string(), secs(100.0), "NoPlat"),
+            MainObbyOutcome::Duplicate
+        );
+
+        store.delete_main_obby_entry("NoPlat", 0);
+        let board = store.main_obby("NoPlat").unwrap();
+        assert_eq!(board.len(), 10);
+        assert_eq!(board[9], ("kim".to_string(), secs(100.0)));
+        assert!(store.main_obby_archive("NoPlat").unwrap().is_empty());
+
+        assert!(store.undo());
+        assert_eq!(store.main_obby("NoPlat").unwrap()[0], ("amy".to_string(), secs(90.0)));
+        assert_eq!(store.main_obby_archive("NoPlat").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn main_obby_categories_come_from_the_config() {
+        let mut store = RecordStore::new();
+        store.add_main_ob_record("amy".to_string(), secs(90.0), "Bounce");
+        store.add_main_ob_record("bob".to_string(), secs(95.0), "Bounce");
+
+        let mut categories = store.main_obby_categories().to_vec();
+        categories[0].cap = 1;
+        categories.push(MainObbyCategory::new("Speedrun", "SR", 3));
+        store.set_main_obby_categories(categories).unwrap();
+
+        assert_eq!(store.main_obby("Bounce").unwrap().len(), 1);
+        assert_eq!(
+            store.add_main_ob_record("amy".to_string(), secs(10.0), "Speedrun"),
+            MainObbyOutcome::Placed(0)
+        );
+
+        assert!(store.undo());
+        assert!(store.undo());
+        assert_eq!(store.main_obby("Speedrun"), None);
+        assert_eq!(store.main_obby("Bounce").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn invalid_main_obby_categories_are_refused() {
+        let mut store = RecordStore::new();
+        let mut categories = store.main_obby_categories().to_vec();
+        categories.push(MainObbyCategory::new("Speedrun", "B", 3));
+
+        assert!(store.set_main_obby_categories(categories).is_err());
+        assert_eq!(store.main_obby_categories(), MainObbyCategory::defaults());
+        assert_eq!(store.revision(), 0);
+    }
+
+    #[test]
+    fn modes_and_categories_change_together_or_not_at_all() {
+        let mut store = RecordStore::new();
+        let mut modes = ObbyMode::defaults();
+        modes.push(ObbyMode::new("Speedrun", "SR"));
+        let mut categories = store.main_obby_categories().to_vec();
+        categories.push(MainObbyCategory::new("Speedrun", "B", 3));
+
+        // The categories share a tag, so the new mode must not be kept either.
+        assert!(store.set_categories(modes.clone(), categories).is_err());
+        assert_eq!(store.modes(), ObbyMode::defaults());
+        assert_eq!(store.revision(), 0);
+
+        let mut categories = store.main_obby_categories().to_vec();
+        categories.push(MainObbyCategory::new("Speedrun", "SR", 3));
+        store.set_categories(modes, categories).unwrap();
+        assert_eq!(store.modes().len(), ObbyMode::defaults().len() + 1);
+        assert!(store.main_obby_category("Speedrun").is_some());
+
+        assert!(store.undo());
+        assert_eq!(store.modes(), ObbyMode::defaults());
+        assert_eq!(store.main_obby_categories(), MainObbyCategory::defaults());
+    }
+
+    #[test]
+    fn unknown_main_obby_category_is_rejected() {
+        let mut store = RecordStore::new();
+        assert_eq!(
+            store.add_main_ob_record("amy".to_string(), secs(10.0), "Speedrun"),
+            MainObbyOutcome::UnknownCategory
+        );
+        assert_eq!(store.revision(), 0);
+    }
+
+    #[test]
+    fn main_obby_keeps_one_best_entry_per_player() {
+        let mut store = RecordStore::new();
+        let add = |store: &mut RecordStore, p: &str, t: Time| {
+            store.add_main_ob_record(p.to_string(), t, "Bounce")
+        };
+
+        assert_eq!(add(&mut store, "amy", secs(100.0)), MainObbyOutcome::Placed(0));
+        assert_eq!(add(&mut store, "amy", secs(100.0)), MainObbyOutcome::Duplicate);
+        assert_eq!(add(&mut store, "amy", secs(105.0)), MainObbyOutcome::SlowerThanOwn(secs(100.0)));
+        assert_eq!(add(&mut store, "bob", secs(90.0)), MainObbyOutcome::Placed(0));
+        assert_eq!(
+            add(&mut store, "amy", secs(80.0)),
+            MainObbyOutcome::Improved { place: 0, previous: secs(100.0) }
+        );
+
+        assert_eq!(
+            store.main_obby("Bounce").unwrap(),
+            &[("amy".to_string(), secs(80.0)), ("bob".to_string(), secs(90.0))]
+        );
+    }
+
+    #[test]
+    fn main_obby_can_allow_several_slots_per_player() {
+        let mut store = RecordStore::new();
+        store.set_main_obby_rule(MainObbyRule::AllowMultiple);
+        store.add_main_ob_record("amy".to_string(), secs(100.0), "NoPlat");
+        store.add_main_ob_record("amy".to_string(), secs(110.0), "NoPlat");
+        store.add_main_ob_record("amy".to_string(), secs(110.0), "NoPlat");
+        assert_eq!(store.main_obby("NoPlat").unwrap().len(), 2);
+
+        store.set_main_obby_rule(MainObbyRule::BestPerPlayer);
+        assert_eq!(store.main_obby("NoPlat").unwrap(), &[("amy".to_string(), secs(100.0))]);
+
+        assert!(store.undo());
+        assert_eq!(store.main_obby("NoPlat").unwrap().len(), 2);
+        assert_eq!(store.main_obby_rule(), MainObbyRule::AllowMultiple);
+    }
+
+    #[test]
+    fn undo_and_redo_an_import_sized_command() {
+        let mut store = RecordStore::new();
+        store.begin("Import");
+        store.add_record_entry("Lava Tower", "Bounce", "amy", secs(12.0));
+        store.add_main_ob_record("bob".to_string(), secs(90.0), "Bounce");
+        store.commit();
+
+        assert!(store.undo());
+        assert!(store.records().is_empty());
+        assert!(store.main_obby("Bounce").unwrap().is_empty());
+        assert!(store.obby_names().is_empty());
+
+        assert!(store.redo());
+        assert_eq!(store.records().len(), 1);
+        assert_eq!(store.main_obby("Bounce").unwrap().len(), 1);
+    }
+}
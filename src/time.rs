@@ -0,0 +1,190 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A run time in whole milliseconds.
+///
+/// Times compare exactly, print with three decimals and are written to JSON
+/// and Lua as seconds, so `83.456` stays `83.456` instead of picking up `f32`
+/// noise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(u64);
+
+impl Time {
+    pub fn from_millis(millis: u64) -> Self {
+        Time(millis)
+    }
+
+    pub fn as_millis(self) -> u64 {
+        self.0
+    }
+
+    /// Rounds to the nearest millisecond. `None` for negative, NaN or
+    /// infinite seconds.
+    pub fn from_secs_f64(seconds: f64) -> Option<Self> {
+        if !seconds.is_finite() || seconds < 0.0 || seconds * 1000.0 > u64::MAX as f64 {
+            return None;
+        }
+        Some(Time((seconds * 1000.0).round() as u64))
+    }
+
+    pub fn as_secs_f64(self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    /// Exactly 9999s is what older imports filled in for times they could not
+    /// read. Longer runs are real, however unlikely.
+    pub fn is_placeholder(self) -> bool {
+        self.0 == 9_999_000
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+/// Why some text is not a time, e.g. for showing under an input field.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTimeError {
+    text: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.text.is_empty() {
+            write!(f, "{}", self.reason)
+        } else {
+            write!(f, "'{}' is not a valid time: {}", self.text, self.reason)
+        }
+    }
+}
+
+impl std::error::Error for ParseTimeError {}
+
+impl FromStr for Time {
+    type Err = ParseTimeError;
+
+    /// Parses `s.mmm`, `m:ss.mmm` or `h:mm:ss.mmm` without going through a
+    /// float. A comma works as decimal separator and surrounding whitespace is
+    /// ignored.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let trimmed = text.trim();
+        let error = |reason| ParseTimeError {
+            text: trimmed.to_string(),
+            reason,
+        };
+
+        if trimmed.is_empty() {
+            return Err(error("enter a time"));
+        }
+        if trimmed.contains('.') && trimmed.contains(',') {
+            return Err(error("use either '.' or ',' for decimals"));
+        }
+
+        let (clock, fraction) = trimmed.split_once(['.', ',']).unwrap_or((trimmed, ""));
+        if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error("expected digits after the decimal separator"));
+        }
+        if fraction.len() > 3 {
+            return Err(error("use at most three decimals"));
+        }
+
+        let fields: Vec<&str> = clock.split(':').collect();
+        if fields.len() > 3 {
+            return Err(error("use s, m:ss or h:mm:ss"));
+        }
+
+        let mut seconds: u64 = 0;
+        for (i, field) in fields.iter().enumerate() {
+            // ".5" is fine, ":30" is not.
+            let empty_ok = fields.len() == 1 && !fraction.is_empty();
+            if field.is_empty() && !empty_ok || !field.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(error("expected a number of seconds"));
+            }
+            let value: u64 = if field.is_empty() {
+                0
+            } else {
+                field.parse().map_err(|_| error("too long"))?
+            };
+            if i > 0 && (field.len() != 2 || value >= 60) {
+                return Err(error("minutes and seconds after ':' need two digits below 60"));
+            }
+            seconds = seconds
+                .checked_mul(60)
+                .and_then(|s| s.checked_add(value))
+                .ok_or_else(|| error("too long"))?;
+        }
+
+        let millis: u64 = format!("{:0<3}", fraction).parse().unwrap_or(0);
+        seconds
+            .checked_mul(1000)
+            .and_then(|ms| ms.checked_add(millis))
+            .map(Time)
+            .ok_or_else(|| error("too long"))
+    }
+}
+
+impl Serialize for Time {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_secs_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let seconds = f64::deserialize(deserializer)?;
+        Time::from_secs_f64(seconds)
+            .ok_or_else(|| serde::de::Error::custom(format!("{} is not a valid time", seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_exactly() {
+        for (text, millis, shown) in [
+            ("83.456", 83_456, "83.456"),
+            ("83.4", 83_400, "83.400"),
+            ("7", 7_000, "7.000"),
+            (".5", 500, "0.500"),
+            (" 12.05 ", 12_050, "12.050"),
+            ("12,05", 12_050, "12.050"),
+            ("1:23.456", 83_456, "83.456"),
+            ("1:02:03", 3_723_000, "3723.000"),
+            ("0:05,5", 5_500, "5.500"),
+        ] {
+            let time: Time = text.parse().unwrap();
+            assert_eq!(time.as_millis(), millis);
+            assert_eq!(time.to_string(), shown);
+        }
+
+        for bad in [
+            "", " ", ".", "1.2345", "-1", "1e3", "abc", "1.2.3", "NaN", "inf", "1:60", "1:5", ":30",
+            "1:2:3:4", "1.5,2", "99999999999999999999",
+        ] {
+            assert!(bad.parse::<Time>().is_err(), "{:?} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn only_the_old_import_sentinel_is_a_placeholder() {
+        for (text, placeholder) in [("9999", true), ("9999.001", false), ("3:00:00", false), ("0", false)] {
+            assert_eq!(text.parse::<Time>().unwrap().is_placeholder(), placeholder, "{}", text);
+        }
+    }
+
+    #[test]
+    fn json_keeps_three_decimals() {
+        let time: Time = "83.456".parse().unwrap();
+        assert_eq!(serde_json::to_string(&time).unwrap(), "83.456");
+
+        // Sessions written with f32 times still load to the intended value.
+        let old: Time = serde_json::from_str("83.45600128173828").unwrap();
+        assert_eq!(old, time);
+    }
+}